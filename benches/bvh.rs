@@ -0,0 +1,91 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use ray_tracing::geometry::bvh::Bvh;
+use ray_tracing::geometry::ray::Ray;
+use ray_tracing::geometry::sphere::Sphere;
+use ray_tracing::geometry::{Hittable, Object, Shape};
+
+const SIZES: [usize; 3] = [10_000, 100_000, 1_000_000];
+const RAYS: usize = 1_000;
+
+// a deterministic xorshift generator of floats in [0, 1)
+fn random(mut state: u32) -> impl FnMut() -> f32 {
+    move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        (state >> 8) as f32 / (1 << 24) as f32
+    }
+}
+
+// spheres scattered through a cube whose volume grows with their number, so
+// that the density of the scene stays the same
+fn scene(n: usize) -> Vec<Object<Sphere>> {
+    let side = 2.0 * (n as f32).cbrt();
+    let mut rand = random(0x9e37_79b9);
+    (0..n)
+        .map(|_| {
+            let center = [rand() * side, rand() * side, rand() * side];
+            Sphere::new(center, 0.3).into_object()
+        })
+        .collect()
+}
+
+fn rays(n: usize) -> Vec<Ray> {
+    let side = 2.0 * (n as f32).cbrt();
+    let mut rand = random(0x85eb_ca6b);
+    (0..RAYS)
+        .map(|_| {
+            let orig = [rand() * side, rand() * side, -side];
+            let to = [rand() * side, rand() * side, rand() * side];
+            Ray::from_points(orig, to)
+        })
+        .collect()
+}
+
+fn build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bvh/build");
+    group.sample_size(10);
+    for &n in SIZES.iter() {
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, &n| {
+            b.iter_batched(|| scene(n), Bvh::new, BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+fn trace(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bvh/trace");
+    group.sample_size(20);
+    group.throughput(Throughput::Elements(RAYS as u64));
+    for &n in SIZES.iter() {
+        let bvh = Bvh::new(scene(n));
+        let rays = rays(n);
+        group.bench_with_input(BenchmarkId::new("hit", n), &rays, |b, rays| {
+            b.iter(|| rays.iter().filter(|&&r| bvh.hit(r).is_some()).count())
+        });
+        group.bench_with_input(BenchmarkId::new("any_hit", n), &rays, |b, rays| {
+            b.iter(|| {
+                rays.iter()
+                    .filter(|&&r| bvh.any_hit(r, f32::INFINITY))
+                    .count()
+            })
+        });
+    }
+
+    // the linear scan the hierarchy replaces, only at the smallest size
+    let n = SIZES[0];
+    let objects = scene(n);
+    let rays = rays(n);
+    group.bench_with_input(BenchmarkId::new("brute_force", n), &rays, |b, rays| {
+        b.iter(|| {
+            rays.iter()
+                .filter(|&&r| objects.iter().any(|o| o.intersect(r).hit().is_some()))
+                .count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, build, trace);
+criterion_main!(benches);
@@ -0,0 +1,128 @@
+use nalgebra::Vector2;
+use std::f32::consts::{FRAC_PI_4, PI};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Lens {
+    /// Everything is in focus.
+    Pinhole,
+    Thin(ThinLens),
+}
+
+impl From<ThinLens> for Lens {
+    fn from(lens: ThinLens) -> Self {
+        Lens::Thin(lens)
+    }
+}
+
+/// A thin lens of radius `aperture` focused at `focus_distance` along the line of
+/// sight. Points at that distance stay sharp, everything else is blurred into the
+/// shape of the aperture.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ThinLens {
+    pub aperture: f32,
+    pub focus_distance: f32,
+    /// Number of diaphragm blades for polygonal bokeh, or `None` for a round aperture.
+    pub blades: Option<u32>,
+    /// Rotation of the polygonal aperture in radians.
+    pub rotation: f32,
+}
+
+impl ThinLens {
+    pub fn new(aperture: f32, focus_distance: f32) -> Self {
+        Self {
+            aperture,
+            focus_distance,
+            blades: None,
+            rotation: 0.0,
+        }
+    }
+
+    pub fn with_blades(mut self, blades: u32, rotation: f32) -> Self {
+        assert!(blades >= 3, "an aperture needs at least 3 blades");
+        self.blades = Some(blades);
+        self.rotation = rotation;
+        self
+    }
+
+    /// Maps a uniform sample in `[0, 1)²` to a uniformly distributed point on the
+    /// aperture, relative to the lens center.
+    pub fn sample_aperture(&self, [u, v]: [f32; 2]) -> Vector2<f32> {
+        let unit = match self.blades {
+            None => concentric_disk(u, v),
+            Some(blades) => polygon(blades, self.rotation, u, v),
+        };
+        unit * self.aperture
+    }
+}
+
+/// Shirley and Chiu's area-preserving mapping from the unit square to the unit disk.
+fn concentric_disk(u: f32, v: f32) -> Vector2<f32> {
+    let (a, b) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return Vector2::zeros();
+    }
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, FRAC_PI_4 * (b / a))
+    } else {
+        (b, 2.0 * FRAC_PI_4 - FRAC_PI_4 * (a / b))
+    };
+    Vector2::new(theta.cos(), theta.sin()) * r
+}
+
+/// Picks one of the triangles fanning out from the center of a regular polygon
+/// inscribed in the unit circle, then samples it uniformly.
+fn polygon(blades: u32, rotation: f32, u: f32, v: f32) -> Vector2<f32> {
+    let scaled = u * blades as f32;
+    let sector = scaled.floor().min(blades as f32 - 1.0);
+    let u = scaled - sector;
+
+    let vertex = |k: f32| {
+        let angle = rotation + 2.0 * PI * k / blades as f32;
+        Vector2::new(angle.cos(), angle.sin())
+    };
+    let edge_point = vertex(sector).lerp(&vertex(sector + 1.0), v);
+    edge_point * u.sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::camera::ThinLens;
+    use itertools::Itertools;
+    use nalgebra::Vector2;
+    use std::f32::consts::PI;
+
+    fn grid() -> impl Iterator<Item = [f32; 2]> {
+        let steps = (0..=16).map(|i| i as f32 / 16.0 * 0.9999);
+        steps.clone().cartesian_product(steps).map(|(u, v)| [u, v])
+    }
+
+    #[test]
+    fn test_disk_aperture() {
+        let lens = ThinLens::new(0.5, 10.0);
+        assert_eq!(lens.sample_aperture([0.5, 0.5]), Vector2::zeros());
+        assert!(grid().all(|s| lens.sample_aperture(s).norm() <= 0.5 + 1e-6));
+        let max = grid()
+            .map(|s| lens.sample_aperture(s).norm())
+            .fold(0.0, f32::max);
+        assert!(max > 0.49);
+    }
+
+    #[test]
+    fn test_hexagonal_aperture() {
+        let lens = ThinLens::new(1.0, 10.0).with_blades(6, 0.0);
+        // the apothem of a regular hexagon inscribed in the unit circle
+        let apothem = (PI / 6.0).cos();
+        for s in grid() {
+            let p = lens.sample_aperture(s);
+            for k in 0..6 {
+                let normal_angle = (2.0 * k as f32 + 1.0) * PI / 6.0;
+                let normal = Vector2::new(normal_angle.cos(), normal_angle.sin());
+                assert!(p.dot(&normal) <= apothem + 1e-5);
+            }
+        }
+        assert!(
+            (lens.sample_aperture([0.9999 / 6.0, 0.9999]) - Vector2::new(0.5, apothem)).norm()
+                < 1e-3
+        );
+    }
+}
@@ -0,0 +1,334 @@
+use crate::geometry::ray::Ray;
+use crate::prelude::*;
+use nalgebra::Vector2;
+
+pub use lens::{Lens, ThinLens};
+pub use projection::Projection;
+
+mod lens;
+mod projection;
+
+/// Builds the world-to-camera transform of an eye at `from` looking at `to`,
+/// with `up` roughly pointing upwards. The camera looks down its negative z axis.
+pub fn view_transform(
+    from: impl Into<Point>,
+    to: impl Into<Point>,
+    up: impl Into<Vector>,
+) -> Isometry {
+    Isometry::look_at_rh(&from.into(), &to.into(), &up.into())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Camera {
+    hsize: usize,
+    vsize: usize,
+    fov: f32,
+    transform: Isometry,
+    projection: Projection,
+    lens: Lens,
+    half_width: f32,
+    half_height: f32,
+    pixel_size: f32,
+}
+
+impl Camera {
+    /// A perspective camera rendering onto a `hsize` by `vsize` canvas with a
+    /// horizontal field of view of `fov` radians (measured along the longer side of
+    /// the canvas).
+    pub fn new([hsize, vsize]: [usize; 2], fov: f32) -> Self {
+        Self {
+            hsize,
+            vsize,
+            fov,
+            transform: Isometry::identity(),
+            projection: Projection::Perspective,
+            lens: Lens::Pinhole,
+            half_width: 0.0,
+            half_height: 0.0,
+            pixel_size: 0.0,
+        }
+        .with_projection(Projection::Perspective)
+    }
+
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        let half_view = projection.half_view(self.fov);
+        let aspect = self.hsize as f32 / self.vsize as f32;
+        let (half_width, half_height) = if aspect >= 1.0 {
+            (half_view, half_view / aspect)
+        } else {
+            (half_view * aspect, half_view)
+        };
+
+        self.projection = projection;
+        self.half_width = half_width;
+        self.half_height = half_height;
+        self.pixel_size = half_width * 2.0 / self.hsize as f32;
+        self
+    }
+
+    /// Sets the world-to-camera transform, usually built with [`view_transform`].
+    pub fn with_transform(mut self, transform: Isometry) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn with_lens(mut self, lens: impl Into<Lens>) -> Self {
+        self.lens = lens.into();
+        self
+    }
+
+    pub fn hsize(&self) -> usize {
+        self.hsize
+    }
+
+    pub fn vsize(&self) -> usize {
+        self.vsize
+    }
+
+    pub fn fov(&self) -> f32 {
+        self.fov
+    }
+
+    pub fn transform(&self) -> &Isometry {
+        &self.transform
+    }
+
+    pub fn projection(&self) -> &Projection {
+        &self.projection
+    }
+
+    pub fn lens(&self) -> &Lens {
+        &self.lens
+    }
+
+    pub fn pixel_size(&self) -> f32 {
+        self.pixel_size
+    }
+
+    /// The ray leaving the center of the lens through the center of pixel `(x, y)`.
+    pub fn ray_for_pixel(&self, x: usize, y: usize) -> Ray {
+        self.ray_through(x, y, [0.5, 0.5], Vector2::zeros())
+    }
+
+    /// A ray through pixel `(x, y)` for one sample of a multi-sampled render.
+    /// `pixel` positions the ray inside the pixel and `lens` picks the point on
+    /// the aperture it leaves from, both as uniform samples in `[0, 1)²`.
+    pub fn ray_for_sample(&self, x: usize, y: usize, pixel: [f32; 2], lens: [f32; 2]) -> Ray {
+        let lens_point = match self.lens {
+            Lens::Pinhole => Vector2::zeros(),
+            Lens::Thin(thin) => thin.sample_aperture(lens),
+        };
+        self.ray_through(x, y, pixel, lens_point)
+    }
+
+    fn ray_through(&self, x: usize, y: usize, [px, py]: [f32; 2], lens_point: Vector2<f32>) -> Ray {
+        // canvas x grows to the right and y downwards, while the camera looks
+        // down -z with +x to the right and +y up
+        let x_offset = (x as f32 + px) * self.pixel_size;
+        let y_offset = (y as f32 + py) * self.pixel_size;
+        let screen = Vector2::new(x_offset - self.half_width, self.half_height - y_offset);
+        let (center, dir) = self.projection.ray(screen);
+
+        // rays from every point of a thin lens converge on the plane of focus
+        let (origin, target) = match self.lens {
+            Lens::Pinhole => (center, center + dir),
+            Lens::Thin(thin) => (
+                center + Vector::new(lens_point.x, lens_point.y, 0.0),
+                center + dir * thin.focus_distance,
+            ),
+        };
+        Ray::from_points(
+            self.transform.inverse_transform_point(&origin),
+            self.transform.inverse_transform_point(&target),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::camera::{view_transform, Camera, ThinLens};
+    use crate::geometry::ray::Ray;
+    use crate::geometry::sphere::Sphere;
+    use crate::geometry::{Hittable, Shape};
+    use crate::prelude::*;
+    use approx::assert_abs_diff_eq;
+    use itertools::Itertools;
+    use nalgebra::{point, vector};
+    use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4, PI};
+
+    #[test]
+    fn test_view_transform_default() {
+        let t = view_transform([0.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]);
+        assert_abs_diff_eq!(t, Isometry::identity());
+    }
+
+    #[test]
+    fn test_view_transform_positive_z() {
+        let t = view_transform([0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]);
+        assert_abs_diff_eq!(
+            t.transform_point(&point![1.0, 2.0, 3.0]),
+            point![-1.0, 2.0, -3.0],
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_view_transform_moves_world() {
+        let t = view_transform([0.0, 0.0, 8.0], [0.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+        assert_abs_diff_eq!(t, Isometry::translation(0.0, 0.0, -8.0));
+    }
+
+    #[test]
+    fn test_view_transform_arbitrary() {
+        let (from, to, up) = (
+            point![1.0, 3.0, 2.0],
+            point![4.0, -2.0, 8.0],
+            vector![1.0, 1.0, 0.0],
+        );
+        let t = view_transform(from, to, up);
+        assert_abs_diff_eq!(t.transform_point(&from), Point::origin(), epsilon = 1e-5);
+        assert_abs_diff_eq!(
+            t.transform_point(&to),
+            point![0.0, 0.0, -(to - from).norm()],
+            epsilon = 1e-5
+        );
+        // an isometry keeps the camera basis orthonormal even when `up` is not
+        // perpendicular to the line of sight, so check the axes rather than a matrix
+        assert_abs_diff_eq!(
+            t.inverse_transform_vector(&-Vector::z()),
+            (to - from).normalize(),
+            epsilon = 1e-6
+        );
+        let up_cam = t.transform_vector(&up);
+        assert_abs_diff_eq!(up_cam.x, 0.0, epsilon = 1e-6);
+        assert!(up_cam.y > 0.0);
+    }
+
+    #[test]
+    fn test_pixel_size() {
+        assert_abs_diff_eq!(Camera::new([200, 125], FRAC_PI_2).pixel_size(), 0.01);
+        assert_abs_diff_eq!(Camera::new([125, 200], FRAC_PI_2).pixel_size(), 0.01);
+    }
+
+    #[test]
+    fn test_ray_through_center() {
+        let c = Camera::new([201, 101], FRAC_PI_2);
+        let r = c.ray_for_pixel(100, 50);
+        assert_abs_diff_eq!(r.orig, Point::origin());
+        assert_abs_diff_eq!(r.dir, vector![0.0, 0.0, -1.0], epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_ray_through_corner() {
+        let c = Camera::new([201, 101], FRAC_PI_2);
+        let r = c.ray_for_pixel(0, 0);
+        assert_abs_diff_eq!(r.orig, Point::origin());
+        assert_abs_diff_eq!(r.dir, vector![-0.66519, 0.33259, -0.66851], epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_world_right_is_canvas_right() {
+        let c = Camera::new([11, 11], FRAC_PI_2);
+        let s = Sphere::default().into_object().translated(2.0, 0.0, -5.0);
+        let hit = (0..11)
+            .cartesian_product(0..11)
+            .filter(|&(x, y)| s.intersect(c.ray_for_pixel(x, y)).hit().is_some())
+            .collect::<Vec<_>>();
+        assert!(!hit.is_empty());
+        assert!(hit.iter().all(|&(x, _)| x > 5), "{:?}", hit);
+    }
+
+    #[test]
+    fn test_ray_transformed_camera() {
+        let c = Camera::new([201, 101], FRAC_PI_2).with_transform(
+            Isometry::rotation(Vector::y() * FRAC_PI_4) * Isometry::translation(0.0, -2.0, 5.0),
+        );
+        let r = c.ray_for_pixel(100, 50);
+        assert_abs_diff_eq!(r.orig, point![0.0, 2.0, -5.0], epsilon = 1e-5);
+        assert_abs_diff_eq!(
+            r.dir,
+            vector![FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2],
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_ray_view_transform_camera() {
+        let c = Camera::new([11, 11], PI / 3.0).with_transform(view_transform(
+            [0.0, 0.0, -5.0],
+            [0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+        ));
+        let r = c.ray_for_pixel(5, 5);
+        assert_abs_diff_eq!(r.orig, point![0.0, 0.0, -5.0], epsilon = 1e-6);
+        assert_abs_diff_eq!(r.dir, vector![0.0, 0.0, 1.0], epsilon = 1e-6);
+    }
+
+    fn distance_to_ray(p: &Point, r: &Ray) -> f32 {
+        let v = p - r.orig;
+        (v - r.dir * v.dot(&r.dir)).norm()
+    }
+
+    fn lens_samples() -> impl Iterator<Item = [f32; 2]> {
+        const STEPS: [f32; 5] = [0.0, 0.2, 0.5, 0.7, 0.95];
+        (0..STEPS.len())
+            .cartesian_product(0..STEPS.len())
+            .map(|(i, j)| [STEPS[i], STEPS[j]])
+    }
+
+    #[test]
+    fn test_thin_lens_focal_plane_is_sharp() {
+        let transform = view_transform([1.0, 2.0, -6.0], [0.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+        let pinhole = Camera::new([40, 30], FRAC_PI_2).with_transform(transform);
+        let focus = (point![1.0, 2.0, -6.0] - Point::origin()).norm();
+
+        for &lens in &[
+            ThinLens::new(0.3, focus),
+            ThinLens::new(0.3, focus).with_blades(6, 0.1),
+        ] {
+            let camera = pinhole.with_lens(lens);
+            for &(x, y) in &[(0, 0), (20, 15), (39, 7)] {
+                // the point the pinhole ray hits on the plane of focus
+                let center = pinhole.ray_for_pixel(x, y);
+                let plane_t = focus
+                    / center
+                        .dir
+                        .dot(&transform.inverse_transform_vector(&-Vector::z()));
+                let focal_point = center.trace(plane_t);
+                for sample in lens_samples() {
+                    let r = camera.ray_for_sample(x, y, [0.5, 0.5], sample);
+                    assert!(distance_to_ray(&focal_point, &r) < 1e-4);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_thin_lens_blurs_out_of_focus() {
+        let camera = Camera::new([40, 30], FRAC_PI_2).with_lens(ThinLens::new(0.3, 5.0));
+        let near = camera.ray_for_pixel(20, 15).trace(2.0);
+        let spread = lens_samples()
+            .map(|sample| {
+                distance_to_ray(&near, &camera.ray_for_sample(20, 15, [0.5, 0.5], sample))
+            })
+            .fold(0.0, f32::max);
+        assert!(spread > 0.05);
+    }
+
+    #[test]
+    fn test_thin_lens_center_matches_pinhole() {
+        let pinhole = Camera::new([40, 30], FRAC_PI_2);
+        let camera = pinhole.with_lens(ThinLens::new(0.3, 5.0));
+        let (a, b) = (pinhole.ray_for_pixel(3, 4), camera.ray_for_pixel(3, 4));
+        assert_abs_diff_eq!(a.orig, b.orig);
+        assert_abs_diff_eq!(a.dir, b.dir, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_pixel_sample_offset() {
+        let camera = Camera::new([201, 101], FRAC_PI_2);
+        let corner = camera.ray_for_sample(100, 50, [0.0, 0.0], [0.5, 0.5]);
+        let expected = Ray::from_points(Point::origin(), point![-1.0 / 201.0, 1.0 / 201.0, -1.0]);
+        assert_abs_diff_eq!(corner.dir, expected.dir, epsilon = 1e-6);
+    }
+}
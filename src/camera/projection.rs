@@ -0,0 +1,137 @@
+use crate::prelude::*;
+use nalgebra::{clamp, Vector2};
+use std::f32::consts::{FRAC_PI_2, PI};
+
+/// How points on the canvas map to rays in camera space. The camera looks down its
+/// negative z axis with +y up.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Projection {
+    /// A pinhole at the origin looking through an image plane at `z = -1`.
+    Perspective,
+    /// Parallel rays leaving a `width` units wide view plane (measured along the
+    /// longer side of the canvas). The field of view is ignored.
+    Orthographic { width: f32 },
+    /// Equidistant fisheye: the distance from the canvas center is proportional to
+    /// the angle from the line of sight, up to half the field of view.
+    Fisheye,
+    /// A full 360° by 180° latitude-longitude panorama, meant for 2:1 canvases. The
+    /// field of view is ignored.
+    Equirectangular,
+}
+
+impl Projection {
+    /// Half the extent of the screen along the longer side of the canvas, in the
+    /// units [`Projection::ray`] expects.
+    pub(super) fn half_view(&self, fov: f32) -> f32 {
+        match *self {
+            Projection::Perspective => (fov / 2.0).tan(),
+            Projection::Orthographic { width } => width / 2.0,
+            Projection::Fisheye => fov / 2.0,
+            Projection::Equirectangular => PI,
+        }
+    }
+
+    /// The origin and direction of the camera-space ray through `screen`. The
+    /// direction of a perspective ray ends on the image plane, the others are unit
+    /// vectors.
+    pub(super) fn ray(&self, screen: Vector2<f32>) -> (Point, Vector) {
+        match *self {
+            Projection::Perspective => (Point::origin(), Vector::new(screen.x, screen.y, -1.0)),
+            Projection::Orthographic { .. } => (Point::new(screen.x, screen.y, 0.0), -Vector::z()),
+            Projection::Fisheye => {
+                let theta = screen.norm();
+                let dir = if theta == 0.0 {
+                    -Vector::z()
+                } else {
+                    let radial = screen * (theta.sin() / theta);
+                    Vector::new(radial.x, radial.y, -theta.cos())
+                };
+                (Point::origin(), dir)
+            }
+            Projection::Equirectangular => {
+                let (lon, lat) = (screen.x, clamp(screen.y, -FRAC_PI_2, FRAC_PI_2));
+                let dir = Vector::new(lat.cos() * lon.sin(), lat.sin(), -lat.cos() * lon.cos());
+                (Point::origin(), dir)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::camera::{Camera, Projection};
+    use crate::geometry::ray::Ray;
+    use crate::prelude::*;
+    use approx::assert_abs_diff_eq;
+    use nalgebra::{point, vector};
+    use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_2, PI};
+
+    // samples the corner of a pixel so that even canvases have an exact center
+    fn ray(camera: &Camera, x: usize, y: usize) -> Ray {
+        camera.ray_for_sample(x, y, [0.0, 0.0], [0.5, 0.5])
+    }
+
+    fn assert_dir(r: &Ray, dir: Vector) {
+        assert_abs_diff_eq!(r.dir, dir, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_orthographic() {
+        let c = Camera::new([200, 100], FRAC_PI_2)
+            .with_projection(Projection::Orthographic { width: 10.0 });
+        assert_abs_diff_eq!(c.pixel_size(), 0.05);
+
+        let center = ray(&c, 100, 50);
+        assert_abs_diff_eq!(center.orig, Point::origin());
+        assert_dir(&center, -Vector::z());
+
+        let corner = ray(&c, 0, 0);
+        assert_abs_diff_eq!(corner.orig, point![-5.0, 2.5, 0.0]);
+        assert_dir(&corner, -Vector::z());
+    }
+
+    #[test]
+    fn test_orthographic_transformed() {
+        let c = Camera::new([200, 100], FRAC_PI_2)
+            .with_projection(Projection::Orthographic { width: 10.0 })
+            .with_transform(Isometry::translation(0.0, 0.0, -8.0));
+        let r = ray(&c, 0, 50);
+        assert_abs_diff_eq!(r.orig, point![-5.0, 0.0, 8.0]);
+        assert_dir(&r, -Vector::z());
+    }
+
+    #[test]
+    fn test_fisheye() {
+        let c = Camera::new([200, 100], PI).with_projection(Projection::Fisheye);
+        assert_dir(&ray(&c, 100, 50), -Vector::z());
+        // the edges of a 180° fisheye look sideways
+        assert_dir(&ray(&c, 0, 50), -Vector::x());
+        // a quarter of the way across is 45° off axis
+        assert_dir(
+            &ray(&c, 150, 50),
+            vector![FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2],
+        );
+        assert_dir(
+            &ray(&c, 100, 0),
+            vector![0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2],
+        );
+    }
+
+    #[test]
+    fn test_equirectangular() {
+        let c = Camera::new([200, 100], FRAC_PI_2).with_projection(Projection::Equirectangular);
+        assert_abs_diff_eq!(c.pixel_size(), PI / 100.0);
+        assert_dir(&ray(&c, 100, 50), -Vector::z());
+        assert_dir(&ray(&c, 50, 50), -Vector::x());
+        assert_dir(&ray(&c, 150, 50), Vector::x());
+        // the left and right edges meet behind the camera
+        assert_dir(&ray(&c, 0, 50), Vector::z());
+        // the top and bottom rows are the poles
+        assert_dir(&ray(&c, 100, 0), Vector::y());
+        assert_dir(&ray(&c, 37, 0), Vector::y());
+        assert_dir(
+            &c.ray_for_sample(100, 99, [0.0, 1.0], [0.5, 0.5]),
+            -Vector::y(),
+        );
+    }
+}
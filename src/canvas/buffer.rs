@@ -0,0 +1,158 @@
+use crate::canvas::Canvas;
+use crate::color::{Color, ColorSpace};
+use image::{ImageResult, RgbImage};
+use std::convert::TryFrom;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+impl Canvas {
+    pub fn to_image_buffer(&self, space: ColorSpace) -> RgbImage {
+        let width = u32::try_from(self.width()).expect("canvas width exceeds u32");
+        let height = u32::try_from(self.height()).expect("canvas height exceeds u32");
+        RgbImage::from_fn(width, height, |x, y| {
+            space
+                .encode(self.pixel_at([x as usize, y as usize]))
+                .into_rgb()
+        })
+    }
+
+    /// Writes the canvas to `path`, choosing the encoder from the file extension.
+    /// `.hdr` and `.pfm` files keep the full floating-point radiance and are always
+    /// linear, so `space` only applies to 8-bit formats.
+    pub fn save<P: AsRef<Path>>(&self, path: P, space: ColorSpace) -> ImageResult<()> {
+        let path = path.as_ref();
+        match extension(path).as_deref() {
+            Some("hdr") => self.write_hdr(BufWriter::new(File::create(path)?)),
+            Some("pfm") => Ok(self.write_pfm(BufWriter::new(File::create(path)?))?),
+            _ => self.to_image_buffer(space).save(path),
+        }
+    }
+
+    pub fn from_image(image: &RgbImage, space: ColorSpace) -> Self {
+        let (width, height) = image.dimensions();
+        let mut canvas = Canvas::new([width as usize, height as usize]);
+        for (x, y, &rgb) in image.enumerate_pixels() {
+            canvas.set_pixel([x as usize, y as usize], space.decode(Color::from_rgb(rgb)));
+        }
+        canvas
+    }
+
+    /// Reads an image from `path`, detecting the format from its extension or contents.
+    /// As with [`Canvas::save`], `space` only applies to 8-bit formats.
+    pub fn open<P: AsRef<Path>>(path: P, space: ColorSpace) -> ImageResult<Self> {
+        let path = path.as_ref();
+        match extension(path).as_deref() {
+            Some("hdr") => Canvas::from_hdr(BufReader::new(File::open(path)?)),
+            Some("pfm") => Ok(Canvas::from_pfm(BufReader::new(File::open(path)?))?),
+            _ => Ok(Canvas::from_image(&image::open(path)?.into_rgb8(), space)),
+        }
+    }
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::Canvas;
+    use crate::color::{Color, ColorSpace};
+    use image::Rgb;
+    use itertools::Itertools;
+    use std::env;
+    use std::fs;
+
+    fn gradient() -> Canvas {
+        let mut canvas = Canvas::new([4, 3]);
+        (0..4).cartesian_product(0..3).for_each(|(x, y)| {
+            let v = (x + y * 4) as f32 / 12.0;
+            canvas.set_pixel([x, y], Color::new([v, 1.0 - v, 0.25]));
+        });
+        canvas
+    }
+
+    #[test]
+    fn test_to_image_buffer() {
+        let mut canvas = Canvas::new([3, 2]);
+        canvas.set_pixel([2, 1], Color::RED.into());
+        let buffer = canvas.to_image_buffer(ColorSpace::Linear);
+        assert_eq!(buffer.dimensions(), (3, 2));
+        assert_eq!(buffer.get_pixel(2, 1), &Color::RED);
+        assert_eq!(buffer.get_pixel(0, 0), &Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_from_image() {
+        let canvas = gradient();
+        for &space in &[ColorSpace::Linear, ColorSpace::Srgb] {
+            let buffer = canvas.to_image_buffer(space);
+            let decoded = Canvas::from_image(&buffer, space);
+            assert_eq!(decoded.width(), 4);
+            assert_eq!(decoded.height(), 3);
+            assert_eq!(decoded.to_image_buffer(space), buffer);
+        }
+    }
+
+    #[test]
+    fn test_from_empty_image() {
+        let canvas = Canvas::from_image(&image::RgbImage::new(0, 0), ColorSpace::Srgb);
+        assert_eq!((canvas.width(), canvas.height()), (0, 0));
+    }
+
+    #[test]
+    fn test_image_buffer_srgb() {
+        let mut canvas = Canvas::new([1, 1]);
+        canvas.set_pixel([0, 0], Color::new([0.5, 0.18, 0.0]));
+        let buffer = canvas.to_image_buffer(ColorSpace::Srgb);
+        assert_eq!(buffer.get_pixel(0, 0), &Rgb([188, 118, 0]));
+    }
+
+    #[test]
+    fn test_save_and_open() {
+        let canvas = gradient();
+        let dir = env::temp_dir().join(format!("ray-tracing-canvas-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        for ext in &["png", "bmp", "tga", "tiff"] {
+            let path = dir.join(format!("gradient.{}", ext));
+            canvas.save(&path, ColorSpace::Srgb).unwrap();
+            let decoded = Canvas::open(&path, ColorSpace::Srgb).unwrap();
+            assert_eq!(
+                decoded.to_image_buffer(ColorSpace::Srgb),
+                canvas.to_image_buffer(ColorSpace::Srgb),
+                "{}",
+                ext
+            );
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_and_open_high_dynamic_range() {
+        let mut canvas = Canvas::new([2, 2]);
+        canvas.set_pixel([0, 0], Color::new([4.0, 0.5, 0.0]));
+        canvas.set_pixel([1, 1], Color::new([0.0, 64.0, 1.0]));
+        let dir = env::temp_dir().join(format!("ray-tracing-canvas-hdr-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        for ext in &["hdr", "pfm"] {
+            let path = dir.join(format!("radiance.{}", ext));
+            canvas.save(&path, ColorSpace::Srgb).unwrap();
+            let decoded = Canvas::open(&path, ColorSpace::Srgb).unwrap();
+            assert_eq!(decoded, canvas, "{}", ext);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_unknown_extension() {
+        let path = env::temp_dir().join("ray-tracing-canvas.unknown");
+        assert!(Canvas::new([1, 1]).save(path, ColorSpace::Srgb).is_err());
+    }
+}
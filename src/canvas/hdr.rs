@@ -0,0 +1,176 @@
+use crate::canvas::ppm::{invalid_data, Tokens};
+use crate::canvas::Canvas;
+use image::codecs::hdr::{HdrDecoder, HdrEncoder};
+use image::{ImageResult, Rgb};
+use nalgebra::Vector3;
+use std::io::{self, BufRead, Read, Write};
+use std::str;
+
+impl Canvas {
+    /// Writes the canvas as a Radiance RGBE image. Negative radiance cannot be
+    /// represented in RGBE and is clamped to zero.
+    pub fn write_hdr<W: Write>(&self, w: W) -> ImageResult<()> {
+        let data = self
+            .pixels()
+            .map(|color| Rgb(color.into_inner().map(|v| v.max(0.0)).into()))
+            .collect::<Vec<_>>();
+        HdrEncoder::new(w).encode(&data, self.width(), self.height())
+    }
+
+    pub fn from_hdr<R: BufRead>(r: R) -> ImageResult<Canvas> {
+        let decoder = HdrDecoder::new(r)?;
+        let meta = decoder.metadata();
+        let (width, height) = (meta.width as usize, meta.height as usize);
+        let mut canvas = Canvas::new([width, height]);
+        for (i, Rgb(rgb)) in decoder.read_image_hdr()?.into_iter().enumerate() {
+            canvas.set_pixel([i % width, i / width], Vector3::from(rgb).into());
+        }
+        Ok(canvas)
+    }
+
+    /// Writes the canvas as a little-endian Portable Float Map, which stores every
+    /// channel as a raw `f32` and is therefore lossless.
+    pub fn write_pfm<W: Write>(&self, mut w: W) -> io::Result<()> {
+        write!(w, "PF\n{} {}\n-1.0\n", self.width(), self.height())?;
+
+        // scanlines are stored bottom-to-top
+        for y in (0..self.height()).rev() {
+            for x in 0..self.width() {
+                for v in self.pixel_at([x, y]).iter() {
+                    w.write_all(&v.to_le_bytes())?;
+                }
+            }
+        }
+
+        w.flush()
+    }
+
+    pub fn from_pfm<R: Read>(mut r: R) -> io::Result<Canvas> {
+        let mut buf = Vec::new();
+        r.read_to_end(&mut buf)?;
+
+        let mut header = Tokens { buf: &buf, pos: 0 };
+        let channels = match header.next_token() {
+            Some(b"PF") => 3,
+            Some(b"Pf") => 1,
+            _ => return Err(invalid_data("unsupported PFM magic number")),
+        };
+        let width = header.next_number()?;
+        let height = header.next_number()?;
+        let scale = header
+            .next_token()
+            .and_then(|token| str::from_utf8(token).ok())
+            .and_then(|token| token.parse::<f32>().ok())
+            .filter(|scale| *scale != 0.0)
+            .ok_or_else(|| invalid_data("malformed PFM scale"))?;
+        let from_bytes = if scale < 0.0 {
+            f32::from_le_bytes
+        } else {
+            f32::from_be_bytes
+        };
+
+        let len = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(channels * 4))
+            .ok_or_else(|| invalid_data("PFM dimensions too large"))?;
+        let data = buf.get(header.pos + 1..).unwrap_or_default();
+        if data.len() < len {
+            return Err(invalid_data("PFM raster is truncated"));
+        }
+
+        let mut canvas = Canvas::new([width, height]);
+        let samples = data[..len]
+            .chunks_exact(4)
+            .map(|b| from_bytes([b[0], b[1], b[2], b[3]]))
+            .collect::<Vec<_>>();
+        for (i, pixel) in samples.chunks_exact(channels).enumerate() {
+            let (x, y) = (i % width, height - 1 - i / width);
+            let rgb = match *pixel {
+                [r, g, b] => Vector3::new(r, g, b),
+                [v] => Vector3::repeat(v),
+                _ => unreachable!(),
+            };
+            canvas.set_pixel([x, y], rgb.into());
+        }
+        Ok(canvas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::Canvas;
+    use crate::color::Color;
+    use approx::assert_relative_eq;
+    use itertools::Itertools;
+
+    fn radiance() -> Canvas {
+        let mut canvas = Canvas::new([9, 2]);
+        (0..9).cartesian_product(0..2).for_each(|(x, y)| {
+            let v = (x + y * 9) as f32;
+            canvas.set_pixel([x, y], Color::new([v * 10.0, 1.0 / (v + 1.0), 0.5]));
+        });
+        canvas
+    }
+
+    #[test]
+    fn test_hdr_round_trip() {
+        let canvas = radiance();
+        let mut buf = Vec::new();
+        canvas.write_hdr(&mut buf).unwrap();
+        let decoded = Canvas::from_hdr(&buf[..]).unwrap();
+        assert_eq!(decoded.width(), 9);
+        assert_eq!(decoded.height(), 2);
+        (0..9).cartesian_product(0..2).for_each(|(x, y)| {
+            let (expected, actual) = (canvas.pixel_at([x, y]), decoded.pixel_at([x, y]));
+            // RGBE shares one exponent, so precision is relative to the brightest channel
+            let tolerance = expected.max() / 128.0;
+            assert_relative_eq!(*actual, *expected, epsilon = tolerance);
+        });
+    }
+
+    #[test]
+    fn test_hdr_clamps_negative() {
+        let mut canvas = Canvas::new([1, 1]);
+        canvas.set_pixel([0, 0], Color::new([-1.0, 2.0, 0.0]));
+        let mut buf = Vec::new();
+        canvas.write_hdr(&mut buf).unwrap();
+        let decoded = Canvas::from_hdr(&buf[..]).unwrap();
+        assert_eq!(decoded.pixel_at([0, 0]), Color::new([0.0, 2.0, 0.0]));
+    }
+
+    #[test]
+    fn test_pfm_round_trip() {
+        let canvas = radiance();
+        let mut buf = Vec::new();
+        canvas.write_pfm(&mut buf).unwrap();
+        assert!(buf.starts_with(b"PF\n9 2\n-1.0\n"));
+        assert_eq!(Canvas::from_pfm(&buf[..]).unwrap(), canvas);
+    }
+
+    #[test]
+    fn test_pfm_bottom_to_top() {
+        let mut pfm = b"PF\n1 2\n1.0\n".to_vec();
+        for v in &[1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0] {
+            pfm.extend_from_slice(&v.to_be_bytes());
+        }
+        let canvas = Canvas::from_pfm(&pfm[..]).unwrap();
+        assert_eq!(canvas.pixel_at([0, 0]), Color::new([4.0, 5.0, 6.0]));
+        assert_eq!(canvas.pixel_at([0, 1]), Color::new([1.0, 2.0, 3.0]));
+    }
+
+    #[test]
+    fn test_pfm_grayscale() {
+        let mut pfm = b"Pf\n2 1\n-1.0\n".to_vec();
+        for v in &[0.25f32, 8.0] {
+            pfm.extend_from_slice(&v.to_le_bytes());
+        }
+        let canvas = Canvas::from_pfm(&pfm[..]).unwrap();
+        assert_eq!(canvas.pixel_at([0, 0]), Color::new([0.25, 0.25, 0.25]));
+        assert_eq!(canvas.pixel_at([1, 0]), Color::new([8.0, 8.0, 8.0]));
+    }
+
+    #[test]
+    fn test_pfm_truncated() {
+        assert!(Canvas::from_pfm(&b"PF\n1 1\n-1.0\n\x00\x00\x00\x00"[..]).is_err());
+    }
+}
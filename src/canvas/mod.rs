@@ -1,6 +1,12 @@
-use crate::color::Color;
+use crate::color::{Color, ToneMap};
 use nalgebra::{Matrix3xX, Vector2};
 
+pub use ppm::PpmFormat;
+
+mod buffer;
+mod hdr;
+mod ppm;
+
 #[derive(Clone, PartialEq, PartialOrd, Debug)]
 pub struct Canvas {
     inner: Matrix3xX<f32>,
@@ -25,6 +31,14 @@ impl Canvas {
         self.inner
     }
 
+    pub fn width(&self) -> usize {
+        self.dim[0]
+    }
+
+    pub fn height(&self) -> usize {
+        self.dim[1]
+    }
+
     fn index_at(&self, [x, y]: [usize; 2]) -> usize {
         let width = self.dim[0];
         x + y * width
@@ -37,12 +51,32 @@ impl Canvas {
     pub fn pixel_at(&self, idx: [usize; 2]) -> Color {
         self.inner.column(self.index_at(idx)).into_owned().into()
     }
+
+    /// Compresses the radiance of every pixel into `[0, 1]` ahead of 8-bit export.
+    pub fn tone_map<T: ToneMap + ?Sized>(&self, op: &T) -> Canvas {
+        self.clone().map_pixels(|color| op.map(color))
+    }
+
+    fn map_pixels(mut self, f: impl Fn(Color) -> Color) -> Canvas {
+        for mut column in self.inner.column_iter_mut() {
+            let mapped = f(column.clone_owned().into());
+            column.copy_from(&*mapped);
+        }
+        self
+    }
+
+    fn pixels(&self) -> impl Iterator<Item = Color> + '_ {
+        self.inner
+            .column_iter()
+            .map(|column| column.into_owned().into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::canvas::Canvas;
-    use crate::color::Color;
+    use crate::color::{Color, Reinhard};
+    use image::Rgb;
     use itertools::Itertools;
 
     #[test]
@@ -60,4 +94,14 @@ mod tests {
         canvas.set_pixel([2, 3], red);
         assert_eq!(canvas.pixel_at([2, 3]), red);
     }
+
+    #[test]
+    fn test_tone_map() {
+        let mut canvas = Canvas::new([2, 1]);
+        canvas.set_pixel([0, 0], Color::new([3.0, 1.0, 0.0]));
+        canvas.set_pixel([1, 0], Color::new([0.5, 0.5, 0.5]));
+        let mapped = canvas.tone_map(&Reinhard);
+        assert_eq!(mapped.pixel_at([0, 0]), Color::new([0.75, 0.5, 0.0]));
+        assert_eq!(mapped.pixel_at([1, 0]).into_rgb(), Rgb([85, 85, 85]));
+    }
 }
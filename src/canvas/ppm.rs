@@ -0,0 +1,289 @@
+use crate::canvas::Canvas;
+use crate::color::ColorSpace;
+use nalgebra::Matrix3xX;
+use std::io::{self, Read, Write};
+use std::str;
+
+const MAX_LINE_LEN: usize = 70;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PpmFormat {
+    /// ASCII samples, wrapped so that no line exceeds 70 characters.
+    P3,
+    /// Binary samples, one byte per channel.
+    P6,
+}
+
+impl PpmFormat {
+    fn magic(self) -> &'static str {
+        match self {
+            PpmFormat::P3 => "P3",
+            PpmFormat::P6 => "P6",
+        }
+    }
+}
+
+impl Canvas {
+    pub fn write_ppm<W: Write>(
+        &self,
+        mut w: W,
+        format: PpmFormat,
+        space: ColorSpace,
+    ) -> io::Result<()> {
+        write!(
+            w,
+            "{}\n{} {}\n255\n",
+            format.magic(),
+            self.width(),
+            self.height()
+        )?;
+
+        match format {
+            PpmFormat::P3 => {
+                let mut line = String::with_capacity(MAX_LINE_LEN);
+                for (i, color) in self.pixels().enumerate() {
+                    for sample in space.encode(color).into_rgb().0.iter() {
+                        let sample = sample.to_string();
+                        if !line.is_empty() && line.len() + 1 + sample.len() > MAX_LINE_LEN {
+                            writeln!(w, "{}", line)?;
+                            line.clear();
+                        }
+                        if !line.is_empty() {
+                            line.push(' ');
+                        }
+                        line.push_str(&sample);
+                    }
+
+                    // every row of the canvas starts on a fresh line
+                    if (i + 1) % self.width() == 0 {
+                        writeln!(w, "{}", line)?;
+                        line.clear();
+                    }
+                }
+            }
+            PpmFormat::P6 => {
+                let data = self
+                    .pixels()
+                    .flat_map(|color| space.encode(color).into_rgb().0)
+                    .collect::<Vec<_>>();
+                w.write_all(&data)?;
+            }
+        }
+
+        w.flush()
+    }
+
+    pub fn to_ppm(&self, format: PpmFormat, space: ColorSpace) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.write_ppm(&mut buf, format, space)
+            .expect("writing to a Vec never fails");
+        buf
+    }
+
+    pub fn from_ppm<R: Read>(mut r: R, space: ColorSpace) -> io::Result<Canvas> {
+        let mut buf = Vec::new();
+        r.read_to_end(&mut buf)?;
+
+        let mut header = Tokens { buf: &buf, pos: 0 };
+        let format = match header.next_token() {
+            Some(b"P3") => PpmFormat::P3,
+            Some(b"P6") => PpmFormat::P6,
+            _ => return Err(invalid_data("unsupported PPM magic number")),
+        };
+        let width = header.next_number()?;
+        let height = header.next_number()?;
+        let max_val = header.next_number()?;
+        if max_val == 0 || max_val > u16::MAX as usize {
+            return Err(invalid_data("PPM maximum value out of range"));
+        }
+
+        let len = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(3))
+            .ok_or_else(|| invalid_data("PPM dimensions too large"))?;
+        let samples = match format {
+            PpmFormat::P3 => (0..len)
+                .map(|_| header.next_number())
+                .collect::<io::Result<Vec<_>>>()?,
+            PpmFormat::P6 => {
+                // exactly one whitespace character separates the header from the raster
+                let data = buf.get(header.pos + 1..).unwrap_or_default();
+                let bytes_per_sample = if max_val < 256 { 1 } else { 2 };
+                if data.len() < len * bytes_per_sample {
+                    return Err(invalid_data("PPM raster is truncated"));
+                }
+                data.chunks_exact(bytes_per_sample)
+                    .take(len)
+                    .map(|s| s.iter().fold(0, |acc, &b| acc << 8 | b as usize))
+                    .collect()
+            }
+        };
+
+        if samples.iter().any(|&s| s > max_val) {
+            return Err(invalid_data("PPM sample exceeds maximum value"));
+        }
+
+        let max_val = max_val as f32;
+        let canvas = Canvas {
+            inner: Matrix3xX::from_iterator(
+                width * height,
+                samples.into_iter().map(|s| s as f32 / max_val),
+            ),
+            dim: [width, height].into(),
+        };
+        Ok(canvas.map_pixels(|color| space.decode(color)))
+    }
+}
+
+pub(super) fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub(super) struct Tokens<'a> {
+    pub(super) buf: &'a [u8],
+    pub(super) pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    pub(super) fn next_token(&mut self) -> Option<&'a [u8]> {
+        loop {
+            match self.buf.get(self.pos)? {
+                b if b.is_ascii_whitespace() => self.pos += 1,
+                b'#' => {
+                    while !matches!(self.buf.get(self.pos), None | Some(b'\n') | Some(b'\r')) {
+                        self.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        let start = self.pos;
+        while matches!(self.buf.get(self.pos), Some(b) if !b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+        Some(&self.buf[start..self.pos])
+    }
+
+    pub(super) fn next_number(&mut self) -> io::Result<usize> {
+        self.next_token()
+            .and_then(|token| str::from_utf8(token).ok())
+            .and_then(|token| token.parse().ok())
+            .ok_or_else(|| invalid_data("malformed number in image header"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::{Canvas, PpmFormat};
+    use crate::color::{Color, ColorSpace};
+    use itertools::Itertools;
+
+    fn ppm_lines(canvas: &Canvas) -> Vec<String> {
+        String::from_utf8(canvas.to_ppm(PpmFormat::P3, ColorSpace::Linear))
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn test_ppm_header() {
+        let canvas = Canvas::new([5, 3]);
+        assert_eq!(ppm_lines(&canvas)[0..3], ["P3", "5 3", "255"]);
+    }
+
+    #[test]
+    fn test_ppm_pixel_data() {
+        let mut canvas = Canvas::new([5, 3]);
+        canvas.set_pixel([0, 0], Color::new([1.5, 0.0, 0.0]));
+        canvas.set_pixel([2, 1], Color::new([0.0, 0.5, 0.0]));
+        canvas.set_pixel([4, 2], Color::new([-0.5, 0.0, 1.0]));
+        assert_eq!(
+            ppm_lines(&canvas)[3..],
+            [
+                "255 0 0 0 0 0 0 0 0 0 0 0 0 0 0",
+                "0 0 0 0 0 0 0 128 0 0 0 0 0 0 0",
+                "0 0 0 0 0 0 0 0 0 0 0 0 0 0 255",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ppm_long_lines() {
+        let mut canvas = Canvas::new([10, 2]);
+        (0..10).cartesian_product(0..2).for_each(|(x, y)| {
+            canvas.set_pixel([x, y], Color::new([1.0, 0.8, 0.6]));
+        });
+        assert_eq!(
+            ppm_lines(&canvas)[3..],
+            [
+                "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204",
+                "153 255 204 153 255 204 153 255 204 153 255 204 153",
+                "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204",
+                "153 255 204 153 255 204 153 255 204 153 255 204 153",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ppm_ends_with_newline() {
+        let canvas = Canvas::new([5, 3]);
+        assert_eq!(
+            canvas.to_ppm(PpmFormat::P3, ColorSpace::Linear).last(),
+            Some(&b'\n')
+        );
+    }
+
+    #[test]
+    fn test_ppm_binary() {
+        let mut canvas = Canvas::new([2, 1]);
+        canvas.set_pixel([1, 0], Color::new([1.0, 0.5, 0.0]));
+        let ppm = canvas.to_ppm(PpmFormat::P6, ColorSpace::Linear);
+        assert_eq!(ppm, b"P6\n2 1\n255\n\x00\x00\x00\xff\x80\x00");
+    }
+
+    #[test]
+    fn test_ppm_round_trip() {
+        let mut canvas = Canvas::new([4, 3]);
+        (0..4).cartesian_product(0..3).for_each(|(x, y)| {
+            let v = (x + y * 4) as f32 / 12.0;
+            canvas.set_pixel([x, y], Color::new([v, 1.0 - v, 0.5]));
+        });
+
+        for &format in &[PpmFormat::P3, PpmFormat::P6] {
+            let ppm = canvas.to_ppm(format, ColorSpace::Linear);
+            let decoded = Canvas::from_ppm(&ppm[..], ColorSpace::Linear).unwrap();
+            assert_eq!(decoded.width(), 4);
+            assert_eq!(decoded.height(), 3);
+            assert_eq!(decoded.to_ppm(format, ColorSpace::Linear), ppm);
+        }
+    }
+
+    #[test]
+    fn test_ppm_read_comments_and_max_value() {
+        let ppm = b"P3\n# a comment\n2 1 # trailing\n15\n15 0 0\n0 15 0\n";
+        let canvas = Canvas::from_ppm(&ppm[..], ColorSpace::Linear).unwrap();
+        assert_eq!(canvas.pixel_at([0, 0]), Color::new([1.0, 0.0, 0.0]));
+        assert_eq!(canvas.pixel_at([1, 0]), Color::new([0.0, 1.0, 0.0]));
+    }
+
+    #[test]
+    fn test_ppm_srgb() {
+        let mut canvas = Canvas::new([1, 1]);
+        canvas.set_pixel([0, 0], Color::new([0.5, 0.0, 1.0]));
+        let ppm = canvas.to_ppm(PpmFormat::P3, ColorSpace::Srgb);
+        assert_eq!(ppm, b"P3\n1 1\n255\n188 0 255\n");
+
+        let decoded = Canvas::from_ppm(&ppm[..], ColorSpace::Srgb).unwrap();
+        assert_eq!(decoded.to_ppm(PpmFormat::P3, ColorSpace::Srgb), ppm);
+    }
+
+    #[test]
+    fn test_ppm_read_invalid() {
+        assert!(Canvas::from_ppm(&b"P5\n1 1\n255\n\x00"[..], ColorSpace::Linear).is_err());
+        assert!(Canvas::from_ppm(&b"P3\n1 1\n255\n0 0"[..], ColorSpace::Linear).is_err());
+        assert!(Canvas::from_ppm(&b"P3\n1 1\n255\n0 0 256"[..], ColorSpace::Linear).is_err());
+        assert!(Canvas::from_ppm(&b"P6\n2 1\n255\n\x00\x00\x00"[..], ColorSpace::Linear).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use image::Rgb;
+use nalgebra::{clamp, Vector3};
+
+use std::ops::{Deref, DerefMut};
+use tap::Conv;
+
+pub use parse::ParseColorError;
+pub use space::{linear_to_srgb, srgb_to_linear, ColorSpace};
+pub use spectrum::{cie_xyz, Radiance, Spectrum, LAMBDA_MAX, LAMBDA_MIN, SPECTRUM_SAMPLES};
+pub use tone_map::{Aces, Clamp, Exposure, Hable, Reinhard, ReinhardExtended, ToneMap};
+
+#[macro_use]
+mod ops;
+mod named;
+mod parse;
+mod space;
+mod spectrum;
+mod tone_map;
+
+macro_rules! def_color {
+    ($name:ident, ($r:literal, $g:literal, $b:literal)) => {
+        pub const $name: Rgb<u8> = Rgb([$r, $g, $b]);
+    };
+}
+
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Default)]
+pub struct Color {
+    rgb: Vector3<f32>,
+}
+
+impl Color {
+    def_color!(BLACK, (0, 0, 0));
+    def_color!(WHITE, (255, 255, 255));
+    def_color!(RED, (255, 0, 0));
+    def_color!(LIME, (0, 255, 0));
+    def_color!(BLUE, (0, 0, 255));
+    def_color!(YELLOW, (255, 255, 0));
+    def_color!(CYAN, (0, 255, 255));
+    def_color!(MAGENTA, (255, 0, 255));
+    def_color!(SILVER, (192, 192, 192));
+    def_color!(GRAY, (128, 128, 128));
+    def_color!(MAROON, (128, 0, 0));
+    def_color!(OLIVE, (128, 128, 0));
+    def_color!(GREEN, (0, 128, 0));
+    def_color!(PURPLE, (128, 0, 128));
+    def_color!(TEAL, (0, 128, 128));
+    def_color!(NAVY, (0, 0, 128));
+
+    pub fn into_inner(self) -> Vector3<f32> {
+        self.rgb
+    }
+}
+
+impl Color {
+    pub const fn new([r, g, b]: [f32; 3]) -> Self {
+        Self {
+            rgb: Vector3::new(r, g, b),
+        }
+    }
+
+    pub fn into_rgb(self) -> Rgb<u8> {
+        let rgb: Vector3<f32> = self.rgb * 256.0;
+        let rgb = rgb.map(|v| clamp(v.floor(), 0.0, 255.0) as u8);
+        Rgb(rgb.data.0[0])
+    }
+
+    pub fn from_rgb(Rgb(rgb): Rgb<u8>) -> Self {
+        let rgb = Vector3::from(rgb).map(|v| v.conv::<f32>()) / 255.0;
+        Self { rgb }
+    }
+
+    /// Relative luminance of a linear color, using the Rec. 709 primaries.
+    pub fn luminance(self) -> f32 {
+        self.rgb.dot(&Vector3::new(0.2126, 0.7152, 0.0722))
+    }
+
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        self + (other - self) * t
+    }
+
+    pub fn multiply(self, other: Color) -> Color {
+        self * other
+    }
+
+    pub fn screen(self, other: Color) -> Color {
+        1.0 - (1.0 - self) * (1.0 - other)
+    }
+
+    /// Overlays `other` onto `self`: multiplies where `self` is dark and screens where it is light.
+    pub fn overlay(self, other: Color) -> Color {
+        self.rgb
+            .zip_map(&other.rgb, |a, b| {
+                if a < 0.5 {
+                    2.0 * a * b
+                } else {
+                    1.0 - 2.0 * (1.0 - a) * (1.0 - b)
+                }
+            })
+            .into()
+    }
+}
+
+impl From<Vector3<f32>> for Color {
+    fn from(rgb: Vector3<f32>) -> Self {
+        Self { rgb }
+    }
+}
+
+impl From<Rgb<u8>> for Color {
+    fn from(rgb: Rgb<u8>) -> Self {
+        Self::from_rgb(rgb)
+    }
+}
+
+impl Deref for Color {
+    type Target = Vector3<f32>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.rgb
+    }
+}
+
+impl DerefMut for Color {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.rgb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use image::Rgb;
+    use nalgebra::vector;
+
+    #[test]
+    fn test_luminance() {
+        assert_abs_diff_eq!(Color::from(vector!(1.0, 1.0, 1.0)).luminance(), 1.0);
+        assert_abs_diff_eq!(Color::from(vector!(0.0, 1.0, 0.0)).luminance(), 0.7152);
+    }
+
+    #[test]
+    fn test_lerp() {
+        let a = Color::new([0.0, 0.5, 1.0]);
+        let b = Color::new([1.0, 0.5, 0.0]);
+        assert_abs_diff_eq!(a.lerp(b, 0.0), a);
+        assert_abs_diff_eq!(a.lerp(b, 1.0), b);
+        assert_abs_diff_eq!(a.lerp(b, 0.25), Color::new([0.25, 0.5, 0.75]));
+    }
+
+    #[test]
+    fn test_blend_modes() {
+        let a = Color::new([0.2, 0.5, 0.8]);
+        let b = Color::new([0.5, 0.5, 0.5]);
+        assert_abs_diff_eq!(a.multiply(b), Color::new([0.1, 0.25, 0.4]));
+        assert_abs_diff_eq!(a.screen(b), Color::new([0.6, 0.75, 0.9]), epsilon = 1e-6);
+        assert_abs_diff_eq!(a.overlay(b), Color::new([0.2, 0.5, 0.8]), epsilon = 1e-6);
+        assert_abs_diff_eq!(
+            a.overlay(Color::new([1.0, 0.0, 0.25])),
+            Color::new([0.4, 0.0, 0.7]),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_to_rgb() {
+        assert_eq!(
+            Color::from(vector!(0.0, 0.0, 0.0)).into_rgb(),
+            Rgb([0, 0, 0])
+        );
+        assert_eq!(
+            Color::from(vector!(1.0, 1.0, 1.0)).into_rgb(),
+            Rgb([255, 255, 255])
+        );
+        assert_eq!(
+            Color::from(vector!(0.5, 0.5, 0.5)).into_rgb(),
+            Rgb([128, 128, 128])
+        );
+    }
+}
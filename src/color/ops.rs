@@ -0,0 +1,181 @@
+use crate::color::Color;
+
+macro_rules! impl_binary_op {
+    ($ty:ident, $field:ident, $op:ident, $fn:ident, $op_assign:ident, $fn_assign:ident, |$a:ident, $b:ident| $body:expr) => {
+        impl ::std::ops::$op for $ty {
+            type Output = $ty;
+
+            fn $fn(self, rhs: $ty) -> $ty {
+                let ($a, $b) = (self.$field, rhs.$field);
+                $ty { $field: $body }
+            }
+        }
+
+        impl ::std::ops::$op<f32> for $ty {
+            type Output = $ty;
+
+            fn $fn(self, rhs: f32) -> $ty {
+                let ($a, $b) = (self.$field, self.$field.map(|_| rhs));
+                $ty { $field: $body }
+            }
+        }
+
+        impl ::std::ops::$op<$ty> for f32 {
+            type Output = $ty;
+
+            fn $fn(self, rhs: $ty) -> $ty {
+                let ($a, $b) = (rhs.$field.map(|_| self), rhs.$field);
+                $ty { $field: $body }
+            }
+        }
+
+        impl ::std::ops::$op_assign for $ty {
+            fn $fn_assign(&mut self, rhs: $ty) {
+                *self = ::std::ops::$op::$fn(*self, rhs);
+            }
+        }
+
+        impl ::std::ops::$op_assign<f32> for $ty {
+            fn $fn_assign(&mut self, rhs: f32) {
+                *self = ::std::ops::$op::$fn(*self, rhs);
+            }
+        }
+    };
+}
+
+/// Implements component-wise arithmetic, `Sum` and the `approx` traits for a color
+/// type wrapping a nalgebra vector of `f32` in the field `$field`.
+macro_rules! impl_color_ops {
+    ($ty:ident, $field:ident) => {
+        impl_binary_op!($ty, $field, Add, add, AddAssign, add_assign, |a, b| a + b);
+        impl_binary_op!($ty, $field, Sub, sub, SubAssign, sub_assign, |a, b| a - b);
+        // `x * y` is the Hadamard (component-wise) product
+        impl_binary_op!($ty, $field, Mul, mul, MulAssign, mul_assign, |a, b| a
+            .component_mul(&b));
+        impl_binary_op!($ty, $field, Div, div, DivAssign, div_assign, |a, b| a
+            .component_div(&b));
+
+        impl ::std::ops::Neg for $ty {
+            type Output = $ty;
+
+            fn neg(self) -> $ty {
+                $ty {
+                    $field: -self.$field,
+                }
+            }
+        }
+
+        impl ::std::iter::Sum for $ty {
+            fn sum<I: Iterator<Item = $ty>>(iter: I) -> $ty {
+                iter.fold($ty::default(), ::std::ops::Add::add)
+            }
+        }
+
+        impl<'a> ::std::iter::Sum<&'a $ty> for $ty {
+            fn sum<I: Iterator<Item = &'a $ty>>(iter: I) -> $ty {
+                iter.copied().sum()
+            }
+        }
+
+        impl ::approx::AbsDiffEq for $ty {
+            type Epsilon = f32;
+
+            fn default_epsilon() -> f32 {
+                <f32 as ::approx::AbsDiffEq>::default_epsilon()
+            }
+
+            fn abs_diff_eq(&self, other: &Self, epsilon: f32) -> bool {
+                ::approx::AbsDiffEq::abs_diff_eq(&self.$field, &other.$field, epsilon)
+            }
+        }
+
+        impl ::approx::RelativeEq for $ty {
+            fn default_max_relative() -> f32 {
+                <f32 as ::approx::RelativeEq>::default_max_relative()
+            }
+
+            fn relative_eq(&self, other: &Self, epsilon: f32, max_relative: f32) -> bool {
+                ::approx::RelativeEq::relative_eq(
+                    &self.$field,
+                    &other.$field,
+                    epsilon,
+                    max_relative,
+                )
+            }
+        }
+
+        impl ::approx::UlpsEq for $ty {
+            fn default_max_ulps() -> u32 {
+                <f32 as ::approx::UlpsEq>::default_max_ulps()
+            }
+
+            fn ulps_eq(&self, other: &Self, epsilon: f32, max_ulps: u32) -> bool {
+                ::approx::UlpsEq::ulps_eq(&self.$field, &other.$field, epsilon, max_ulps)
+            }
+        }
+    };
+}
+
+impl_color_ops!(Color, rgb);
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use approx::{assert_abs_diff_eq, assert_relative_eq, assert_ulps_eq};
+
+    #[test]
+    fn test_add_sub() {
+        let c1 = Color::new([0.9, 0.6, 0.75]);
+        let c2 = Color::new([0.7, 0.1, 0.25]);
+        assert_abs_diff_eq!(c1 + c2, Color::new([1.6, 0.7, 1.0]));
+        assert_abs_diff_eq!(c1 - c2, Color::new([0.2, 0.5, 0.5]), epsilon = 1e-6);
+        assert_abs_diff_eq!(-c2, Color::new([-0.7, -0.1, -0.25]));
+    }
+
+    #[test]
+    fn test_mul_div() {
+        let c = Color::new([0.2, 0.3, 0.4]);
+        assert_abs_diff_eq!(c * 2.0, Color::new([0.4, 0.6, 0.8]));
+        assert_abs_diff_eq!(2.0 * c, Color::new([0.4, 0.6, 0.8]));
+        assert_abs_diff_eq!(c / 2.0, Color::new([0.1, 0.15, 0.2]));
+
+        let c1 = Color::new([1.0, 0.2, 0.4]);
+        let c2 = Color::new([0.9, 1.0, 0.1]);
+        assert_abs_diff_eq!(c1 * c2, Color::new([0.9, 0.2, 0.04]), epsilon = 1e-6);
+        assert_abs_diff_eq!(c1 / c2, Color::new([1.0 / 0.9, 0.2, 4.0]), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_assign() {
+        let mut c = Color::new([0.5, 0.5, 0.5]);
+        c += Color::new([0.5, 0.0, 0.25]);
+        c *= 2.0;
+        c -= 1.0;
+        c /= Color::new([1.0, 2.0, 0.5]);
+        assert_abs_diff_eq!(c, Color::new([1.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn test_sum() {
+        let colors = [
+            Color::new([0.1, 0.2, 0.3]),
+            Color::new([0.4, 0.5, 0.6]),
+            Color::new([0.5, 0.3, 0.1]),
+        ];
+        assert_abs_diff_eq!(colors.iter().sum::<Color>(), Color::new([1.0, 1.0, 1.0]));
+        assert_eq!(
+            Vec::<Color>::new().into_iter().sum::<Color>(),
+            Color::default()
+        );
+    }
+
+    #[test]
+    fn test_approx() {
+        let c = Color::new([0.1, 0.2, 0.3]);
+        let d = c + 1e-7;
+        assert_abs_diff_eq!(c, d);
+        assert_relative_eq!(c, d);
+        assert_ulps_eq!(c, d);
+        assert!(approx::abs_diff_ne!(c, c + 1e-3));
+    }
+}
@@ -0,0 +1,166 @@
+use crate::color::Color;
+use image::Rgb;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseColorError {
+    InvalidHex(String),
+    InvalidTriplet(String),
+    UnknownName(String),
+}
+
+impl Display for ParseColorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseColorError::InvalidHex(s) => write!(f, "invalid hex color `{}`", s),
+            ParseColorError::InvalidTriplet(s) => write!(f, "invalid rgb() triplet `{}`", s),
+            ParseColorError::UnknownName(s) => write!(f, "unknown color name `{}`", s),
+        }
+    }
+}
+
+impl Error for ParseColorError {}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let (len, scale) = match hex.len() {
+        // `#rgb` is shorthand for `#rrggbb`
+        3 => (1, 0x11),
+        // the alpha channel of `#rrggbbaa` is ignored, colors are opaque
+        6 | 8 => (2, 1),
+        _ => return None,
+    };
+    let channel = |i: usize| u8::from_str_radix(&hex[i * len..(i + 1) * len], 16).ok();
+    let rgb = [
+        channel(0)? * scale,
+        channel(1)? * scale,
+        channel(2)? * scale,
+    ];
+    Some(Color::from_rgb(Rgb(rgb)))
+}
+
+fn parse_triplet(args: &str) -> Option<Color> {
+    let mut rgb = [0.0; 3];
+    let mut parts = args.split(',');
+    for v in rgb.iter_mut() {
+        *v = parts.next()?.trim().parse().ok()?;
+    }
+    match parts.next() {
+        None => Some(Color::new(rgb)),
+        Some(_) => None,
+    }
+}
+
+/// Accepts `#rgb`, `#rrggbb`, `#rrggbbaa`, `rgb(r, g, b)` with floating-point
+/// components, or a CSS/X11 color name. Hex digits are mapped to `[0, 1]` the same
+/// way as [`Color::from_rgb`].
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(hex) = s.strip_prefix('#') {
+            parse_hex(hex).ok_or_else(|| ParseColorError::InvalidHex(s.to_owned()))
+        } else if let Some(args) = s
+            .strip_prefix("rgb(")
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            parse_triplet(args).ok_or_else(|| ParseColorError::InvalidTriplet(s.to_owned()))
+        } else {
+            Color::named(s).ok_or_else(|| ParseColorError::UnknownName(s.to_owned()))
+        }
+    }
+}
+
+/// Formats as `rgb(r, g, b)` with the shortest representation of each component
+/// that parses back to the same value.
+impl Display for Color {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "rgb({:?}, {:?}, {:?})", self.x, self.y, self.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::{Color, ParseColorError};
+    use image::Rgb;
+
+    fn rgb(s: &str) -> Rgb<u8> {
+        s.parse::<Color>().unwrap().into_rgb()
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(rgb("#ff8800"), Rgb([255, 136, 0]));
+        assert_eq!(rgb("#FF8800"), Rgb([255, 136, 0]));
+        assert_eq!(rgb("#f80"), Rgb([255, 136, 0]));
+        assert_eq!(rgb("#ff880080"), Rgb([255, 136, 0]));
+        assert_eq!(rgb("  #008080 "), Color::TEAL);
+    }
+
+    #[test]
+    fn test_parse_triplet() {
+        assert_eq!(
+            "rgb(1.0, 0.5, 0)".parse::<Color>(),
+            Ok(Color::new([1.0, 0.5, 0.0]))
+        );
+        assert_eq!(
+            "rgb(2.5,-1e-3,.25)".parse::<Color>(),
+            Ok(Color::new([2.5, -1e-3, 0.25]))
+        );
+    }
+
+    #[test]
+    fn test_parse_named() {
+        assert_eq!("teal".parse::<Color>(), Ok(Color::TEAL.into()));
+        assert_eq!(rgb("CornflowerBlue"), Rgb([100, 149, 237]));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            "#ff88".parse::<Color>(),
+            Err(ParseColorError::InvalidHex(_))
+        ));
+        assert!(matches!(
+            "#gg8800".parse::<Color>(),
+            Err(ParseColorError::InvalidHex(_))
+        ));
+        assert!(matches!(
+            "rgb(1, 2)".parse::<Color>(),
+            Err(ParseColorError::InvalidTriplet(_))
+        ));
+        assert!(matches!(
+            "rgb(1, 2, 3, 4)".parse::<Color>(),
+            Err(ParseColorError::InvalidTriplet(_))
+        ));
+        assert!(matches!(
+            "rgb(1, x, 3)".parse::<Color>(),
+            Err(ParseColorError::InvalidTriplet(_))
+        ));
+        assert_eq!(
+            "blurple".parse::<Color>(),
+            Err(ParseColorError::UnknownName("blurple".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let colors = [
+            Color::new([1.0, 0.5, 0.0]),
+            Color::new([0.1, 1.0 / 3.0, -2.5e-8]),
+            Color::from_rgb(Color::OLIVE),
+        ];
+        for color in colors.iter() {
+            assert_eq!(color.to_string().parse::<Color>(), Ok(*color));
+        }
+        assert_eq!(
+            Color::new([1.0, 0.5, 0.0]).to_string(),
+            "rgb(1.0, 0.5, 0.0)"
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use crate::color::Color;
+
+/// Transfer function applied to 8-bit samples when a canvas is exported or imported.
+/// Radiance inside a `Canvas` is always linear.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    Linear,
+    Srgb,
+}
+
+impl ColorSpace {
+    /// Converts linear radiance into this color space.
+    pub fn encode(self, color: Color) -> Color {
+        match self {
+            ColorSpace::Linear => color,
+            ColorSpace::Srgb => color.map(linear_to_srgb).into(),
+        }
+    }
+
+    /// Converts a color expressed in this color space back into linear radiance.
+    pub fn decode(self, color: Color) -> Color {
+        match self {
+            ColorSpace::Linear => color,
+            ColorSpace::Srgb => color.map(srgb_to_linear).into(),
+        }
+    }
+}
+
+/// The sRGB opto-electronic transfer function (IEC 61966-2-1).
+pub fn linear_to_srgb(v: f32) -> f32 {
+    if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// The sRGB electro-optical transfer function, the exact inverse of [`linear_to_srgb`].
+pub fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.040_45 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::{linear_to_srgb, srgb_to_linear, Color, ColorSpace};
+    use approx::assert_abs_diff_eq;
+    use image::Rgb;
+
+    #[test]
+    fn test_known_srgb_values() {
+        assert_abs_diff_eq!(linear_to_srgb(0.0), 0.0);
+        assert_abs_diff_eq!(linear_to_srgb(1.0), 1.0, epsilon = 1e-6);
+        assert_abs_diff_eq!(linear_to_srgb(0.5), 0.735_357, epsilon = 1e-5);
+        assert_abs_diff_eq!(linear_to_srgb(0.18), 0.461_356, epsilon = 1e-5);
+        assert_abs_diff_eq!(linear_to_srgb(0.001), 0.012_92, epsilon = 1e-7);
+        assert_abs_diff_eq!(srgb_to_linear(128.0 / 255.0), 0.215_861, epsilon = 1e-5);
+        assert_abs_diff_eq!(srgb_to_linear(0.02), 0.001_548, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_piecewise_continuity() {
+        assert_abs_diff_eq!(
+            linear_to_srgb(0.003_130_8),
+            linear_to_srgb(0.003_130_8 + 1e-7),
+            epsilon = 1e-5
+        );
+        assert_abs_diff_eq!(
+            srgb_to_linear(0.040_45),
+            srgb_to_linear(0.040_45 + 1e-6),
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for i in 0..=100 {
+            let v = i as f32 / 100.0;
+            assert_abs_diff_eq!(srgb_to_linear(linear_to_srgb(v)), v, epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_color_space() {
+        let gray = Color::new([0.5, 0.5, 0.5]);
+        assert_eq!(ColorSpace::Linear.encode(gray), gray);
+        assert_eq!(
+            ColorSpace::Srgb.encode(gray).into_rgb(),
+            Rgb([188, 188, 188])
+        );
+        assert_abs_diff_eq!(
+            *ColorSpace::Srgb.decode(Color::from_rgb(Rgb([188, 188, 188]))),
+            *gray,
+            epsilon = 5e-3
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use crate::color::Color;
+use nalgebra::{Matrix3, SVector, Vector3};
+use std::iter::Sum;
+use std::ops::{Add, Div, Mul, Sub};
+
+pub const LAMBDA_MIN: f32 = 380.0;
+pub const LAMBDA_MAX: f32 = 780.0;
+pub const SPECTRUM_SAMPLES: usize = 41;
+const LAMBDA_STEP: f32 = (LAMBDA_MAX - LAMBDA_MIN) / (SPECTRUM_SAMPLES - 1) as f32;
+
+/// Quantity carried along rays while shading. Shading code written against this
+/// trait runs in RGB mode when instantiated with [`Color`] and in spectral mode
+/// with [`Spectrum`], so the mode is chosen per render by its type parameter.
+pub trait Radiance:
+    Copy
+    + Default
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Mul<f32, Output = Self>
+    + Div<f32, Output = Self>
+    + Sum
+{
+    fn from_color(color: Color) -> Self;
+
+    fn to_color(self) -> Color;
+}
+
+impl Radiance for Color {
+    fn from_color(color: Color) -> Self {
+        color
+    }
+
+    fn to_color(self) -> Color {
+        self
+    }
+}
+
+/// A spectral power distribution point-sampled every 10nm over 380–780nm.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Spectrum {
+    samples: SVector<f32, SPECTRUM_SAMPLES>,
+}
+
+impl_color_ops!(Spectrum, samples);
+
+impl Default for Spectrum {
+    fn default() -> Self {
+        Spectrum::constant(0.0)
+    }
+}
+
+impl Radiance for Spectrum {
+    fn from_color(color: Color) -> Self {
+        Spectrum::from_color(color)
+    }
+
+    fn to_color(self) -> Color {
+        Spectrum::to_color(&self)
+    }
+}
+
+impl Spectrum {
+    pub fn constant(v: f32) -> Self {
+        Spectrum {
+            samples: SVector::repeat(v),
+        }
+    }
+
+    /// Samples `f` at every wavelength of the spectrum, in nanometers.
+    pub fn from_fn(f: impl Fn(f32) -> f32) -> Self {
+        Spectrum {
+            samples: SVector::from_fn(|i, _| f(Spectrum::wavelength(i))),
+        }
+    }
+
+    pub fn wavelength(idx: usize) -> f32 {
+        LAMBDA_MIN + idx as f32 * LAMBDA_STEP
+    }
+
+    pub fn wavelengths() -> impl Iterator<Item = f32> {
+        (0..SPECTRUM_SAMPLES).map(Spectrum::wavelength)
+    }
+
+    pub fn samples(&self) -> &SVector<f32, SPECTRUM_SAMPLES> {
+        &self.samples
+    }
+
+    /// Linearly interpolates the spectrum at `lambda`; zero outside 380–780nm.
+    pub fn sample(&self, lambda: f32) -> f32 {
+        match Spectrum::bracket(lambda) {
+            Some((i, t)) if t > 0.0 => self.samples[i] * (1.0 - t) + self.samples[i + 1] * t,
+            Some((i, _)) => self.samples[i],
+            None => 0.0,
+        }
+    }
+
+    /// Light of a single wavelength, such as one dispersed ray, carrying `power`
+    /// in total. Its color is the spectral locus hue at `lambda`.
+    pub fn monochromatic(lambda: f32, power: f32) -> Self {
+        let mut spectrum = Spectrum::default();
+        if let Some((i, t)) = Spectrum::bracket(lambda) {
+            let density = power / LAMBDA_STEP;
+            spectrum.samples[i] += density * (1.0 - t);
+            if t > 0.0 {
+                spectrum.samples[i + 1] += density * t;
+            }
+        }
+        spectrum
+    }
+
+    fn bracket(lambda: f32) -> Option<(usize, f32)> {
+        if !(LAMBDA_MIN..=LAMBDA_MAX).contains(&lambda) {
+            return None;
+        }
+        let x = (lambda - LAMBDA_MIN) / LAMBDA_STEP;
+        let i = (x.floor() as usize).min(SPECTRUM_SAMPLES - 1);
+        Some((i, x - i as f32))
+    }
+
+    /// Upsamples a linear RGB reflectance with Smits' method ("An RGB-to-Spectrum
+    /// Conversion for Reflectances", 1999), which yields smooth, mostly non-negative
+    /// spectra that round-trip to approximately the same RGB.
+    pub fn from_color(color: Color) -> Self {
+        let [r, g, b] = [color.x, color.y, color.z];
+        let basis =
+            |table: &[f32; 10]| Spectrum::from_fn(|lambda| smits::interpolate(table, lambda));
+
+        let (white, mut spectrum) = (basis(&smits::WHITE), Spectrum::default());
+        if r <= g && r <= b {
+            spectrum += white * r;
+            if g <= b {
+                spectrum += basis(&smits::CYAN) * (g - r) + basis(&smits::BLUE) * (b - g);
+            } else {
+                spectrum += basis(&smits::CYAN) * (b - r) + basis(&smits::GREEN) * (g - b);
+            }
+        } else if g <= r && g <= b {
+            spectrum += white * g;
+            if r <= b {
+                spectrum += basis(&smits::MAGENTA) * (r - g) + basis(&smits::BLUE) * (b - r);
+            } else {
+                spectrum += basis(&smits::MAGENTA) * (b - g) + basis(&smits::RED) * (r - b);
+            }
+        } else {
+            spectrum += white * b;
+            if r <= g {
+                spectrum += basis(&smits::YELLOW) * (r - b) + basis(&smits::GREEN) * (g - r);
+            } else {
+                spectrum += basis(&smits::YELLOW) * (g - b) + basis(&smits::RED) * (r - g);
+            }
+        }
+        spectrum
+    }
+
+    /// CIE 1931 XYZ tristimulus values, normalized so that a constant spectrum of 1
+    /// has a luminance `Y` of 1.
+    pub fn to_xyz(&self) -> Vector3<f32> {
+        let (xyz, y_norm) = Spectrum::wavelengths().zip(self.samples.iter()).fold(
+            (Vector3::zeros(), 0.0),
+            |(xyz, y_norm), (lambda, &s)| {
+                let cmf = cie_xyz(lambda);
+                (xyz + cmf * s, y_norm + cmf.y)
+            },
+        );
+        xyz / y_norm
+    }
+
+    /// Converts to linear sRGB, white balanced so that a constant spectrum of 1
+    /// maps to `(1, 1, 1)`.
+    pub fn to_color(&self) -> Color {
+        let white = xyz_to_linear_srgb() * Spectrum::constant(1.0).to_xyz();
+        (xyz_to_linear_srgb() * self.to_xyz())
+            .component_div(&white)
+            .into()
+    }
+}
+
+#[rustfmt::skip]
+fn xyz_to_linear_srgb() -> Matrix3<f32> {
+    Matrix3::new(
+         3.240_454, -1.537_139, -0.498_531,
+        -0.969_266,  1.876_011,  0.041_556,
+         0.055_643, -0.204_026,  1.057_225,
+    )
+}
+
+/// The CIE 1931 2° color matching functions at `lambda` nanometers, using the
+/// multi-lobe Gaussian fit of Wyman, Sloan and Shirley (2013).
+pub fn cie_xyz(lambda: f32) -> Vector3<f32> {
+    let g = |mu: f32, sigma_lo: f32, sigma_hi: f32| {
+        let sigma = if lambda < mu { sigma_lo } else { sigma_hi };
+        (-0.5 * ((lambda - mu) / sigma).powi(2)).exp()
+    };
+    Vector3::new(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+mod smits {
+    // ten 34nm bins over 380–720nm, values at the bin centers
+    const FIRST_CENTER: f32 = 397.0;
+    const BIN_WIDTH: f32 = 34.0;
+
+    pub(super) const WHITE: [f32; 10] = [
+        1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+    ];
+    pub(super) const CYAN: [f32; 10] = [
+        0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+    ];
+    pub(super) const MAGENTA: [f32; 10] = [
+        1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+    ];
+    pub(super) const YELLOW: [f32; 10] = [
+        0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+    ];
+    pub(super) const RED: [f32; 10] = [
+        0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+    ];
+    pub(super) const GREEN: [f32; 10] = [
+        0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+    ];
+    pub(super) const BLUE: [f32; 10] = [
+        1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+    ];
+
+    /// Piecewise-linear interpolation between bin centers, held constant past the ends.
+    pub(super) fn interpolate(table: &[f32; 10], lambda: f32) -> f32 {
+        let x = ((lambda - FIRST_CENTER) / BIN_WIDTH).max(0.0);
+        let i = x.floor() as usize;
+        if i >= table.len() - 1 {
+            return table[table.len() - 1];
+        }
+        let t = x - i as f32;
+        table[i] * (1.0 - t) + table[i + 1] * t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::{Color, Radiance, Spectrum};
+    use approx::{assert_abs_diff_eq, assert_relative_eq};
+
+    #[test]
+    fn test_wavelengths() {
+        let wavelengths = Spectrum::wavelengths().collect::<Vec<_>>();
+        assert_eq!(wavelengths.first(), Some(&380.0));
+        assert_eq!(wavelengths.last(), Some(&780.0));
+        assert_abs_diff_eq!(wavelengths[1] - wavelengths[0], 10.0);
+    }
+
+    #[test]
+    fn test_sample() {
+        let ramp = Spectrum::from_fn(|lambda| lambda);
+        assert_abs_diff_eq!(ramp.sample(555.0), 555.0, epsilon = 1e-3);
+        assert_abs_diff_eq!(ramp.sample(780.0), 780.0);
+        assert_eq!(ramp.sample(800.0), 0.0);
+    }
+
+    #[test]
+    fn test_constant_to_xyz() {
+        assert_abs_diff_eq!(Spectrum::constant(1.0).to_xyz().y, 1.0, epsilon = 1e-6);
+        assert_abs_diff_eq!(Spectrum::constant(0.5).to_xyz().y, 0.5, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_gray_round_trip() {
+        for &v in &[0.0, 0.18, 0.5, 1.0] {
+            let gray = Color::new([v, v, v]);
+            assert_abs_diff_eq!(Spectrum::from_color(gray).to_color(), gray, epsilon = 2e-3);
+        }
+    }
+
+    #[test]
+    fn test_color_round_trip() {
+        let colors = [
+            Color::new([1.0, 0.0, 0.0]),
+            Color::new([0.0, 1.0, 0.0]),
+            Color::new([0.0, 0.0, 1.0]),
+            Color::new([0.8, 0.6, 0.2]),
+            Color::new([0.1, 0.5, 0.9]),
+        ];
+        for color in colors.iter() {
+            let round_trip = Spectrum::from_color(*color).to_color();
+            assert_abs_diff_eq!(round_trip, *color, epsilon = 0.15);
+            assert_eq!(round_trip.imax(), color.imax());
+        }
+    }
+
+    #[test]
+    fn test_monochromatic() {
+        assert_eq!(Spectrum::monochromatic(900.0, 1.0), Spectrum::default());
+        assert_abs_diff_eq!(
+            Spectrum::monochromatic(555.0, 1.0).samples().sum() * 10.0,
+            1.0
+        );
+        assert_eq!(Spectrum::monochromatic(650.0, 1.0).to_color().imax(), 0);
+        assert_eq!(Spectrum::monochromatic(530.0, 1.0).to_color().imax(), 1);
+        assert_eq!(Spectrum::monochromatic(450.0, 1.0).to_color().imax(), 2);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Spectrum::from_fn(|lambda| lambda / 100.0);
+        let b = Spectrum::constant(2.0);
+        assert_relative_eq!((a * b).sample(500.0), 10.0);
+        assert_relative_eq!((a + b - b).sample(600.0), 6.0);
+        assert_relative_eq!((a / 2.0).sample(400.0), 2.0);
+        assert_relative_eq!([a, b].iter().sum::<Spectrum>().sample(700.0), 9.0);
+    }
+
+    fn shade<R: Radiance>(albedo: Color, light: Color) -> Color {
+        (R::from_color(albedo) * R::from_color(light) * 0.5).to_color()
+    }
+
+    #[test]
+    fn test_radiance_modes_agree() {
+        let albedo = Color::new([0.7, 0.7, 0.7]);
+        let light = Color::new([1.0, 1.0, 1.0]);
+        assert_abs_diff_eq!(
+            shade::<Color>(albedo, light),
+            shade::<Spectrum>(albedo, light),
+            epsilon = 2e-3
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use crate::color::Color;
+use nalgebra::clamp;
+
+/// Maps unbounded linear radiance into the displayable `[0, 1]` range.
+pub trait ToneMap {
+    fn map(&self, color: Color) -> Color;
+
+    /// Scales the radiance by `2^stops` before tone mapping.
+    fn with_exposure(self, stops: f32) -> Exposure<Self>
+    where
+        Self: Sized,
+    {
+        Exposure {
+            scale: stops.exp2(),
+            inner: self,
+        }
+    }
+}
+
+impl<T: ToneMap + ?Sized> ToneMap for &T {
+    fn map(&self, color: Color) -> Color {
+        (**self).map(color)
+    }
+}
+
+fn per_channel(color: Color, f: impl Fn(f32) -> f32) -> Color {
+    color.map(|v| clamp(f(v.max(0.0)), 0.0, 1.0)).into()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Exposure<T> {
+    pub scale: f32,
+    pub inner: T,
+}
+
+impl<T: ToneMap> ToneMap for Exposure<T> {
+    fn map(&self, color: Color) -> Color {
+        self.inner.map(color * self.scale)
+    }
+}
+
+/// Hard clip to `[0, 1]`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Clamp;
+
+impl ToneMap for Clamp {
+    fn map(&self, color: Color) -> Color {
+        per_channel(color, |v| v)
+    }
+}
+
+/// `c / (1 + c)`, which never reaches white.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Reinhard;
+
+impl ToneMap for Reinhard {
+    fn map(&self, color: Color) -> Color {
+        per_channel(color, |v| v / (1.0 + v))
+    }
+}
+
+/// Reinhard with a white point: radiance at or above `white` maps to 1.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ReinhardExtended {
+    pub white: f32,
+}
+
+impl ToneMap for ReinhardExtended {
+    fn map(&self, color: Color) -> Color {
+        let white_sq = self.white.powi(2);
+        per_channel(color, |v| v * (1.0 + v / white_sq) / (1.0 + v))
+    }
+}
+
+/// Narkowicz's fit of the ACES filmic reference curve.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Aces;
+
+impl ToneMap for Aces {
+    fn map(&self, color: Color) -> Color {
+        const A: f32 = 2.51;
+        const B: f32 = 0.03;
+        const C: f32 = 2.43;
+        const D: f32 = 0.59;
+        const E: f32 = 0.14;
+        per_channel(color, |v| (v * (A * v + B)) / (v * (C * v + D) + E))
+    }
+}
+
+/// John Hable's filmic curve from Uncharted 2, normalized so that `white` maps to 1.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Hable {
+    pub white: f32,
+}
+
+impl Hable {
+    fn curve(v: f32) -> f32 {
+        const A: f32 = 0.15;
+        const B: f32 = 0.50;
+        const C: f32 = 0.10;
+        const D: f32 = 0.20;
+        const E: f32 = 0.02;
+        const F: f32 = 0.30;
+        (v * (A * v + C * B) + D * E) / (v * (A * v + B) + D * F) - E / F
+    }
+}
+
+impl Default for Hable {
+    fn default() -> Self {
+        Hable { white: 11.2 }
+    }
+}
+
+impl ToneMap for Hable {
+    fn map(&self, color: Color) -> Color {
+        let white_scale = Hable::curve(self.white).recip();
+        per_channel(color, |v| Hable::curve(v) * white_scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::{Aces, Clamp, Color, Hable, Reinhard, ReinhardExtended, ToneMap};
+    use approx::assert_abs_diff_eq;
+
+    fn gray(v: f32) -> Color {
+        Color::new([v, v, v])
+    }
+
+    fn map_gray(op: impl ToneMap, v: f32) -> f32 {
+        op.map(gray(v)).x
+    }
+
+    #[test]
+    fn test_clamp() {
+        assert_eq!(
+            Clamp.map(Color::new([1.5, -0.5, 0.25])),
+            Color::new([1.0, 0.0, 0.25])
+        );
+    }
+
+    #[test]
+    fn test_exposure() {
+        assert_abs_diff_eq!(map_gray(Clamp.with_exposure(1.0), 0.25), 0.5);
+        assert_abs_diff_eq!(map_gray(Clamp.with_exposure(-2.0), 2.0), 0.5);
+    }
+
+    #[test]
+    fn test_reinhard() {
+        assert_abs_diff_eq!(map_gray(Reinhard, 0.0), 0.0);
+        assert_abs_diff_eq!(map_gray(Reinhard, 1.0), 0.5);
+        assert!(map_gray(Reinhard, 1e6) < 1.0);
+    }
+
+    #[test]
+    fn test_reinhard_extended() {
+        let op = ReinhardExtended { white: 4.0 };
+        assert_abs_diff_eq!(map_gray(op, 0.0), 0.0);
+        assert_abs_diff_eq!(map_gray(op, 4.0), 1.0);
+        assert_abs_diff_eq!(map_gray(op, 100.0), 1.0);
+        assert!(map_gray(op, 1.0) > map_gray(Reinhard, 1.0));
+    }
+
+    #[test]
+    fn test_aces() {
+        assert_abs_diff_eq!(map_gray(Aces, 0.0), 0.0);
+        assert_abs_diff_eq!(map_gray(Aces, 1.0), 2.54 / 3.16, epsilon = 1e-6);
+        assert_abs_diff_eq!(map_gray(Aces, 1e3), 1.0);
+    }
+
+    #[test]
+    fn test_hable() {
+        let op = Hable::default();
+        assert_abs_diff_eq!(map_gray(op, 0.0), 0.0, epsilon = 1e-6);
+        assert_abs_diff_eq!(map_gray(op, 11.2), 1.0, epsilon = 1e-6);
+        assert_abs_diff_eq!(map_gray(op, 50.0), 1.0);
+    }
+
+    #[test]
+    fn test_monotonic() {
+        let ops: [&dyn ToneMap; 4] = [
+            &Reinhard,
+            &ReinhardExtended { white: 8.0 },
+            &Aces,
+            &Hable::default(),
+        ];
+        for op in ops.iter() {
+            let samples = (0..100)
+                .map(|i| map_gray(op, i as f32 * 0.1))
+                .collect::<Vec<_>>();
+            assert!(samples.windows(2).all(|w| w[0] <= w[1]));
+        }
+    }
+}
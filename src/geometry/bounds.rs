@@ -0,0 +1,333 @@
+use crate::geometry::ray::Ray;
+use crate::prelude::*;
+use crate::util::transform::GenericTransform;
+
+/// An axis-aligned bounding box. Empty bounds have `min` above `max`, and shapes
+/// without an extent along some axis have infinite bounds along it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Bounds {
+    pub fn new(min: [f32; 3], max: [f32; 3]) -> Self {
+        Self {
+            min: min.into(),
+            max: max.into(),
+        }
+    }
+
+    /// The bounds of nothing, which is the identity of [`Bounds::union`].
+    pub fn empty() -> Self {
+        Self {
+            min: Point::from(Vector::repeat(f32::INFINITY)),
+            max: Point::from(Vector::repeat(f32::NEG_INFINITY)),
+        }
+    }
+
+    pub fn infinite() -> Self {
+        Self {
+            min: Point::from(Vector::repeat(f32::NEG_INFINITY)),
+            max: Point::from(Vector::repeat(f32::INFINITY)),
+        }
+    }
+
+    pub fn from_points(points: impl IntoIterator<Item = Point>) -> Self {
+        points
+            .into_iter()
+            .fold(Bounds::empty(), |bounds, p| bounds.with_point(p))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|axis| self.min[axis] > self.max[axis])
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.min
+            .iter()
+            .chain(self.max.iter())
+            .all(|c| c.is_finite())
+    }
+
+    pub fn center(&self) -> Point {
+        nalgebra::center(&self.min, &self.max)
+    }
+
+    pub fn size(&self) -> Vector {
+        self.max - self.min
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let size = self.size();
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    pub fn with_point(&self, point: Point) -> Self {
+        Self {
+            min: self.min.inf(&point),
+            max: self.max.sup(&point),
+        }
+    }
+
+    pub fn union(&self, other: &Bounds) -> Self {
+        Self {
+            min: self.min.inf(&other.min),
+            max: self.max.sup(&other.max),
+        }
+    }
+
+    pub fn intersection(&self, other: &Bounds) -> Self {
+        Self {
+            min: self.min.sup(&other.min),
+            max: self.max.inf(&other.max),
+        }
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        (0..3).all(|axis| (self.min[axis]..=self.max[axis]).contains(&point[axis]))
+    }
+
+    /// The distance from `point` to the nearest point of the box, zero inside it.
+    pub fn distance(&self, point: &Point) -> f32 {
+        (self.min - point)
+            .sup(&(point - self.max))
+            .sup(&Vector::zeros())
+            .norm()
+    }
+
+    pub fn corners(&self) -> [Point; 8] {
+        let (lo, hi) = (self.min, self.max);
+        [
+            Point::new(lo.x, lo.y, lo.z),
+            Point::new(hi.x, lo.y, lo.z),
+            Point::new(lo.x, hi.y, lo.z),
+            Point::new(hi.x, hi.y, lo.z),
+            Point::new(lo.x, lo.y, hi.z),
+            Point::new(hi.x, lo.y, hi.z),
+            Point::new(lo.x, hi.y, hi.z),
+            Point::new(hi.x, hi.y, hi.z),
+        ]
+    }
+
+    /// The bounds of the transformed box. Infinite bounds stay infinite along
+    /// every axis, since a rotation may carry their extent anywhere.
+    pub fn transform(&self, transform: &impl GenericTransform<f32, 3>) -> Self {
+        if self.is_empty() {
+            Bounds::empty()
+        } else if !self.is_finite() {
+            Bounds::infinite()
+        } else {
+            Bounds::from_points(self.corners().iter().map(|p| transform.transform_point(p)))
+        }
+    }
+
+    /// The distances at which the line of `ray` enters and leaves the box.
+    pub fn intersect(&self, ray: &Ray) -> Option<(f32, f32)> {
+        // intersect the three slabs between opposite faces, the ray is inside the
+        // box where it is inside all of them
+        let mut t_min = f32::NEG_INFINITY;
+        let mut t_max = f32::INFINITY;
+        // transformed rays may have any length, so parallel is relative to it
+        let parallel = 1e-9 * ray.dir.norm();
+        for axis in 0..3 {
+            let (lo, hi) = (
+                self.min[axis] - ray.orig[axis],
+                self.max[axis] - ray.orig[axis],
+            );
+            if ray.dir[axis].abs() <= parallel {
+                if lo > 0.0 || hi < 0.0 {
+                    return None;
+                }
+                continue;
+            }
+            let (t0, t1) = (lo / ray.dir[axis], hi / ray.dir[axis]);
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+        }
+        if t_min <= t_max {
+            Some((t_min, t_max))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::bounds::Bounds;
+    use crate::geometry::cone::Cone;
+    use crate::geometry::cube::Cube;
+    use crate::geometry::cylinder::Cylinder;
+    use crate::geometry::mesh::Mesh;
+    use crate::geometry::plane::Plane;
+    use crate::geometry::ray::Ray;
+    use crate::geometry::sphere::Sphere;
+    use crate::geometry::triangle::Triangle;
+    use crate::geometry::{Hittable, Shape};
+    use crate::prelude::*;
+    use approx::assert_abs_diff_eq;
+    use nalgebra::{point, Rotation3};
+    use std::f32::consts::{FRAC_PI_4, SQRT_2};
+
+    #[test]
+    fn test_empty_and_infinite() {
+        assert!(Bounds::empty().is_empty());
+        assert!(!Bounds::empty().contains(&Point::origin()));
+        assert_eq!(Bounds::empty().surface_area(), 0.0);
+        assert!(!Bounds::infinite().is_finite());
+        assert!(Bounds::infinite().contains(&point![1e30, -1e30, 0.0]));
+        let b = Bounds::new([-1.0, 0.0, 0.0], [1.0, 2.0, 3.0]);
+        assert_eq!(Bounds::empty().union(&b), b);
+        assert_eq!(b.union(&Bounds::infinite()), Bounds::infinite());
+    }
+
+    #[test]
+    fn test_union_and_intersection() {
+        let a = Bounds::new([-1.0, -2.0, -3.0], [1.0, 2.0, 3.0]);
+        let b = Bounds::new([0.0, 1.0, -5.0], [4.0, 3.0, 1.0]);
+        assert_eq!(
+            a.union(&b),
+            Bounds::new([-1.0, -2.0, -5.0], [4.0, 3.0, 3.0])
+        );
+        assert_eq!(
+            a.intersection(&b),
+            Bounds::new([0.0, 1.0, -3.0], [1.0, 2.0, 1.0])
+        );
+        let c = Bounds::new([5.0, 5.0, 5.0], [6.0, 6.0, 6.0]);
+        assert!(a.intersection(&c).is_empty());
+    }
+
+    #[test]
+    fn test_from_points_and_contains() {
+        let b = Bounds::from_points(vec![
+            point![-5.0, 2.0, 0.0],
+            point![7.0, 0.0, -3.0],
+            point![0.0, 1.0, 4.0],
+        ]);
+        assert_eq!(b, Bounds::new([-5.0, 0.0, -3.0], [7.0, 2.0, 4.0]));
+        assert_eq!(b.center(), point![1.0, 1.0, 0.5]);
+        assert!(b.contains(&point![7.0, 2.0, 4.0]));
+        assert!(b.contains(&point![0.0, 0.5, 0.0]));
+        assert!(!b.contains(&point![0.0, 2.5, 0.0]));
+        assert_eq!(b.distance(&point![0.0, 1.0, 0.0]), 0.0);
+        assert_eq!(b.distance(&point![0.0, 5.0, 8.0]), 5.0);
+        assert_eq!(
+            Bounds::empty().distance(&point![0.0, 0.0, 0.0]),
+            f32::INFINITY
+        );
+        assert_eq!(
+            b.surface_area(),
+            2.0 * (12.0 * 2.0 + 2.0 * 7.0 + 7.0 * 12.0)
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let b = Bounds::new([-1.0, -1.0, -1.0], [1.0, 1.0, 1.0]);
+        let rotation = Rotation3::from_axis_angle(&Vector::y_axis(), FRAC_PI_4);
+        let t = b.transform(&rotation);
+        assert_abs_diff_eq!(t.min, point![-SQRT_2, -1.0, -SQRT_2], epsilon = 1e-6);
+        assert_abs_diff_eq!(t.max, point![SQRT_2, 1.0, SQRT_2], epsilon = 1e-6);
+        let t = b.transform(&Translation::new(1.0, 2.0, 3.0));
+        assert_eq!(t, Bounds::new([0.0, 1.0, 2.0], [2.0, 3.0, 4.0]));
+        assert!(Bounds::empty().transform(&rotation).is_empty());
+        assert_eq!(
+            Plane::default().bounds().transform(&rotation),
+            Bounds::infinite()
+        );
+    }
+
+    #[test]
+    fn test_intersect() {
+        let b = Bounds::new([-1.0, -1.0, -1.0], [1.0, 1.0, 1.0]);
+        let r = Ray::new([-5.0, 0.5, 0.0], [1.0, 0.0, 0.0]);
+        assert_eq!(b.intersect(&r), Some((4.0, 6.0)));
+        let r = Ray::new([-5.0, 0.5, 0.0], [1.0, 1.0, 0.0]);
+        assert_eq!(b.intersect(&r), None);
+        // parallel to a slab, outside of it
+        let r = Ray::new([-5.0, 2.0, 0.0], [1.0, 0.0, 0.0]);
+        assert_eq!(b.intersect(&r), None);
+        let r = Ray::new([0.0, 0.0, 0.0], [0.0, 0.0, 1.0]);
+        assert_eq!(b.intersect(&r), Some((-1.0, 1.0)));
+        let r = Ray::new([0.0, 5.0, 0.0], [0.0, -1.0, 0.0]);
+        assert_eq!(
+            Bounds::infinite().intersect(&r),
+            Some((f32::NEG_INFINITY, f32::INFINITY))
+        );
+        // the short direction of a ray into a hugely scaled-up box
+        let r = Ray {
+            orig: point![-5.0, 0.5, 0.0],
+            dir: Vector::new(1e-10, 0.0, 0.0),
+        };
+        let (t_min, t_max) = b.intersect(&r).unwrap();
+        assert_abs_diff_eq!(t_min, 4e10, epsilon = 1e4);
+        assert_abs_diff_eq!(t_max, 6e10, epsilon = 1e4);
+    }
+
+    #[test]
+    fn test_shape_bounds() {
+        assert_eq!(
+            Sphere::new([1.0, 2.0, 3.0], 2.0).bounds(),
+            Bounds::new([-1.0, 0.0, 1.0], [3.0, 4.0, 5.0])
+        );
+        assert_eq!(
+            Cube.bounds(),
+            Bounds::new([-1.0, -1.0, -1.0], [1.0, 1.0, 1.0])
+        );
+        assert_eq!(
+            Cylinder::new(-5.0, 3.0, true).bounds(),
+            Bounds::new([-1.0, -5.0, -1.0], [1.0, 3.0, 1.0])
+        );
+        let infinite = Cylinder::default().bounds();
+        assert_eq!((infinite.min.x, infinite.max.x), (-1.0, 1.0));
+        assert_eq!(infinite.min.y, f32::NEG_INFINITY);
+        assert_eq!(
+            Cone::new(-5.0, 3.0, false).bounds(),
+            Bounds::new([-5.0, -5.0, -5.0], [5.0, 3.0, 5.0])
+        );
+        assert!(!Cone::default().bounds().is_finite());
+        assert_eq!(Plane::default().bounds(), Bounds::infinite());
+        assert_eq!(
+            Triangle::new([-3.0, 7.0, 2.0], [6.0, 2.0, -4.0], [2.0, -1.0, -1.0]).bounds(),
+            Bounds::new([-3.0, -1.0, -4.0], [6.0, 7.0, 2.0])
+        );
+        let mesh = Mesh::new(
+            vec![
+                point![0.0, 0.0, 0.0],
+                point![2.0, 0.0, 1.0],
+                point![0.0, 3.0, 0.0],
+            ],
+            vec![[0, 1, 2]],
+        );
+        assert_eq!(mesh.bounds(), Bounds::new([0.0, 0.0, 0.0], [2.0, 3.0, 1.0]));
+        assert!(Mesh::new(Vec::new(), Vec::new()).bounds().is_empty());
+    }
+
+    #[test]
+    fn test_object_bounds() {
+        let obj = Sphere::default()
+            .into_object()
+            .scaled(2.0, 1.0, 1.0)
+            .translated(0.0, 5.0, 0.0);
+        assert_eq!(
+            obj.bounds(),
+            Bounds::new([-2.0, 4.0, -1.0], [2.0, 6.0, 1.0])
+        );
+        let boxed: Box<dyn Hittable> = Box::new(obj);
+        assert_eq!(
+            boxed.bounds(),
+            Bounds::new([-2.0, 4.0, -1.0], [2.0, 6.0, 1.0])
+        );
+        assert_eq!(
+            Plane::default()
+                .into_object()
+                .translated(0.0, 1.0, 0.0)
+                .bounds(),
+            Bounds::infinite()
+        );
+    }
+}
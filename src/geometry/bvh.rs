@@ -0,0 +1,620 @@
+use crate::geometry::bounds::Bounds;
+use crate::geometry::ray::Ray;
+use crate::geometry::{is_on_surface, HitContext, HitData, Hittable, Id, Intersection};
+use crate::prelude::*;
+use crate::util::transform::GenericTransform;
+use nalgebra::Unit;
+use std::iter::FromIterator;
+
+const BINS: usize = 16;
+const MAX_LEAF_SIZE: usize = 4;
+// the cost of testing a box, relative to intersecting an object
+const TRAVERSAL_COST: f32 = 0.125;
+
+/// A bounding volume hierarchy over a set of hittables, so that a ray only
+/// intersects the objects whose boxes it passes through. Objects with infinite
+/// or empty bounds, such as planes, are kept aside and always intersected.
+#[derive(Debug)]
+pub struct Bvh<H = Box<dyn Hittable>> {
+    id: Id,
+    objects: Vec<H>,
+    unbounded: Vec<u32>,
+    hierarchy: Hierarchy,
+}
+
+impl<H: Hittable> Bvh<H> {
+    pub fn new(objects: Vec<H>) -> Self {
+        let bounds = objects.iter().map(H::bounds).collect::<Vec<_>>();
+        let (bounded, unbounded): (Vec<u32>, Vec<u32>) =
+            (0..objects.len() as u32).partition(|&i| {
+                let b = &bounds[i as usize];
+                !b.is_empty() && b.is_finite()
+            });
+        Self {
+            id: Id::new(),
+            objects,
+            unbounded,
+            hierarchy: Hierarchy::new(&bounds, bounded),
+        }
+    }
+
+    pub fn objects(&self) -> &[H] {
+        &self.objects
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// The closest hit at a non-negative distance, like [`Intersection::hit`]
+    /// without gathering the hits of every object along the ray.
+    pub fn hit(&self, ray: Ray) -> Option<(f32, HitContext<'_>)> {
+        let mut closest: Option<(f32, HitContext)> = None;
+        let mut visit = |i: u32| {
+            let inter = self.objects[i as usize].intersect(ray);
+            match (inter.hit(), closest.as_ref()) {
+                (Some((t, _)), Some(&(c, _))) if t >= c => {}
+                (Some((t, ctx)), _) => closest = Some((t, ctx.clone())),
+                (None, _) => {}
+            }
+            closest.as_ref().map_or(f32::INFINITY, |c| c.0)
+        };
+        let t_max = self.unbounded.iter().fold(f32::INFINITY, |_, &i| visit(i));
+        self.hierarchy.traverse(&ray, 0.0, t_max, visit);
+        closest
+    }
+
+    /// Whether anything is hit at a distance in `[0, t_max)`, stopping at the
+    /// first hit found. Meant for shadow rays.
+    pub fn any_hit(&self, ray: Ray, t_max: f32) -> bool {
+        let is_hit = |&i: &u32| {
+            self.objects[i as usize]
+                .intersect(ray)
+                .between(0.0..t_max)
+                .next()
+                .is_some()
+        };
+        if self.unbounded.iter().any(is_hit) {
+            return true;
+        }
+        let mut found = false;
+        self.hierarchy.traverse(&ray, 0.0, t_max, |i| {
+            found = is_hit(&i);
+            // a negative limit ends the traversal
+            if found {
+                f32::NEG_INFINITY
+            } else {
+                t_max
+            }
+        });
+        found
+    }
+
+    // the object whose surface passes through `point`, or else the one with the
+    // nearest bounds, skipping objects with nothing to hit, such as empty groups
+    fn object_at(&self, point: Point) -> Option<&H> {
+        let on_surface = |&i: &u32| is_on_surface(&self.objects[i as usize], point);
+        let bounds = |i: u32| self.objects[i as usize].bounds();
+        self.unbounded
+            .iter()
+            .copied()
+            .find(on_surface)
+            .or_else(|| {
+                self.hierarchy.nearest(&point, 0.0, |i| {
+                    if on_surface(&i) {
+                        0.0
+                    } else {
+                        f32::INFINITY
+                    }
+                })
+            })
+            // infinite bounds are at no distance from anything
+            .or_else(|| {
+                self.unbounded
+                    .iter()
+                    .copied()
+                    .find(|&i| !bounds(i).is_empty())
+            })
+            .or_else(|| {
+                self.hierarchy
+                    .nearest(&point, f32::INFINITY, |i| bounds(i).distance(&point))
+            })
+            .map(|i| &self.objects[i as usize])
+    }
+}
+
+/// A flattened bounding volume hierarchy over items known by their index, such
+/// as the objects of a [`Bvh`] or the triangles of a mesh.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Hierarchy {
+    // the union of the bounds of the items, unpadded
+    bounds: Bounds,
+    // item indices in the order the leaves of `nodes` refer to them
+    order: Vec<u32>,
+    nodes: Vec<Node>,
+}
+
+/// A node of the flattened hierarchy. Leaves hold `count > 0` items from
+/// `order[first..]`; inner nodes are followed by their left child, and `first`
+/// is the index of the right one.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Node {
+    bounds: Bounds,
+    first: u32,
+    count: u32,
+}
+
+impl Hierarchy {
+    /// Builds the hierarchy over `items`, indices into `bounds` whose bounds
+    /// must be finite and non-empty.
+    pub(super) fn new(bounds: &[Bounds], mut items: Vec<u32>) -> Self {
+        let mut nodes = Vec::new();
+        if !items.is_empty() {
+            let centroids = bounds.iter().map(Bounds::center).collect::<Vec<_>>();
+            build(&mut nodes, bounds, &centroids, &mut items, 0);
+        }
+        Self {
+            bounds: items
+                .iter()
+                .fold(Bounds::empty(), |b, &i| b.union(&bounds[i as usize])),
+            order: items,
+            nodes,
+        }
+    }
+
+    pub(super) fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    /// Calls `visit` on the items whose boxes `ray` passes through between
+    /// `t_min` and `t_max`, nearer nodes first. `visit` returns a new `t_max`,
+    /// which can only shrink, and the traversal ends once it drops below `t_min`.
+    pub(super) fn traverse(
+        &self,
+        ray: &Ray,
+        t_min: f32,
+        mut t_max: f32,
+        mut visit: impl FnMut(u32) -> f32,
+    ) {
+        let mut stack = Vec::new();
+        if let Some(t) = self.enter(0, ray, t_min, t_max) {
+            stack.push((0, t));
+        }
+        while let Some((i, t_enter)) = stack.pop() {
+            // `t_max` may have shrunk since the node was pushed
+            if t_enter > t_max {
+                continue;
+            }
+            let node = &self.nodes[i];
+            if node.count > 0 {
+                for &item in self.leaf(node) {
+                    t_max = t_max.min(visit(item));
+                    if t_max < t_min {
+                        return;
+                    }
+                }
+                continue;
+            }
+            let (left, right) = (i + 1, node.first as usize);
+            match (
+                self.enter(left, ray, t_min, t_max),
+                self.enter(right, ray, t_min, t_max),
+            ) {
+                (Some(l), Some(r)) => {
+                    // visit the nearer child first, it is more likely to shorten
+                    // the ray for the other one
+                    let (near, far) = if l <= r {
+                        ((left, l), (right, r))
+                    } else {
+                        ((right, r), (left, l))
+                    };
+                    stack.push(far);
+                    stack.push(near);
+                }
+                (Some(l), None) => stack.push((left, l)),
+                (None, Some(r)) => stack.push((right, r)),
+                (None, None) => {}
+            }
+        }
+    }
+
+    /// The item closest to `point` by `distance`, if any is within
+    /// `max_distance`. The distance to an item must be at least that to its
+    /// bounds, so that farther nodes can be skipped, and NaN is never closest.
+    pub(super) fn nearest(
+        &self,
+        point: &Point,
+        max_distance: f32,
+        mut distance: impl FnMut(u32) -> f32,
+    ) -> Option<u32> {
+        let mut nearest = None;
+        let mut limit = max_distance;
+        let mut stack = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push((0, self.nodes[0].bounds.distance(point)));
+        }
+        while let Some((i, d)) = stack.pop() {
+            // `limit` may have shrunk since the node was pushed
+            if d > limit || (nearest.is_some() && d >= limit) {
+                continue;
+            }
+            let node = &self.nodes[i];
+            if node.count > 0 {
+                for &item in self.leaf(node) {
+                    let d = distance(item);
+                    if d < limit || (nearest.is_none() && d <= limit) {
+                        nearest = Some(item);
+                        limit = d;
+                    }
+                }
+                continue;
+            }
+            let (left, right) = (i + 1, node.first as usize);
+            let l = (left, self.nodes[left].bounds.distance(point));
+            let r = (right, self.nodes[right].bounds.distance(point));
+            let (near, far) = if l.1 <= r.1 { (l, r) } else { (r, l) };
+            stack.push(far);
+            stack.push(near);
+        }
+        nearest
+    }
+
+    fn leaf(&self, node: &Node) -> &[u32] {
+        &self.order[node.first as usize..(node.first + node.count) as usize]
+    }
+
+    // the distance at which the ray enters a node, if it overlaps the node
+    // between `t_min` and `t_max`
+    fn enter(&self, node: usize, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32> {
+        let (t0, t1) = self.nodes.get(node)?.bounds.intersect(ray)?;
+        if t1 < t_min || t0 > t_max {
+            None
+        } else {
+            Some(t0.max(t_min))
+        }
+    }
+}
+
+// binned surface area heuristic: the centroids are sorted into bins along the
+// longest axis, and the split between bins minimizing the summed surface area of
+// each side times its item count is taken
+fn build(
+    nodes: &mut Vec<Node>,
+    bounds: &[Bounds],
+    centroids: &[Point],
+    order: &mut [u32],
+    offset: usize,
+) {
+    let node_bounds = order
+        .iter()
+        .fold(Bounds::empty(), |b, &i| b.union(&bounds[i as usize]));
+    let node = nodes.len();
+    nodes.push(Node {
+        bounds: padded(node_bounds),
+        first: offset as u32,
+        count: order.len() as u32,
+    });
+    if order.len() == 1 {
+        return;
+    }
+
+    let extent = Bounds::from_points(order.iter().map(|&i| centroids[i as usize]));
+    let axis = extent.size().imax();
+    let (lo, size) = (extent.min[axis], extent.size()[axis]);
+    if size <= 0.0 {
+        // all the centroids coincide, no split separates them
+        return;
+    }
+    let bin = |i: u32| {
+        let offset = (centroids[i as usize][axis] - lo) / size;
+        ((offset * BINS as f32) as usize).min(BINS - 1)
+    };
+
+    let mut bins = [(Bounds::empty(), 0); BINS];
+    for &i in order.iter() {
+        let (b, count) = &mut bins[bin(i)];
+        *b = b.union(&bounds[i as usize]);
+        *count += 1;
+    }
+    // the cost of the objects right of each split, sweeping from the right
+    let mut right_costs = [0.0; BINS];
+    let mut right = (Bounds::empty(), 0);
+    for k in (1..BINS).rev() {
+        right = (right.0.union(&bins[k].0), right.1 + bins[k].1);
+        right_costs[k - 1] = if right.1 > 0 {
+            right.0.surface_area() * right.1 as f32
+        } else {
+            f32::INFINITY
+        };
+    }
+    let mut left = (Bounds::empty(), 0);
+    let mut best = (f32::INFINITY, 0);
+    for k in 0..BINS - 1 {
+        left = (left.0.union(&bins[k].0), left.1 + bins[k].1);
+        if left.1 == 0 {
+            continue;
+        }
+        let cost = left.0.surface_area() * left.1 as f32 + right_costs[k];
+        if cost < best.0 {
+            best = (cost, k);
+        }
+    }
+
+    let area = node_bounds.surface_area();
+    let leaf_cost = area * order.len() as f32;
+    if order.len() <= MAX_LEAF_SIZE && TRAVERSAL_COST * area + best.0 >= leaf_cost {
+        return;
+    }
+    // the first and last bins are never empty, so neither side is
+    let mid = itertools::partition(order.iter_mut(), |&i| bin(i) <= best.1);
+
+    let (left, right) = order.split_at_mut(mid);
+    nodes[node].count = 0;
+    build(nodes, bounds, centroids, left, offset);
+    nodes[node].first = nodes.len() as u32;
+    build(nodes, bounds, centroids, right, offset + mid);
+}
+
+// leave some slack for rounding, so that rays grazing an object lying on the
+// faces of the box are not culled
+fn padded(bounds: Bounds) -> Bounds {
+    let pad = bounds.min.coords.abs().sup(&bounds.max.coords.abs()) * 1e-5;
+    Bounds {
+        min: bounds.min - pad,
+        max: bounds.max + pad,
+    }
+}
+
+impl<H: Hittable> FromIterator<H> for Bvh<H> {
+    fn from_iter<I: IntoIterator<Item = H>>(iter: I) -> Self {
+        Bvh::new(iter.into_iter().collect())
+    }
+}
+
+impl<H: Hittable> Hittable for Bvh<H> {
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn intersect(&self, ray: Ray) -> Intersection<'_> {
+        let mut hits = Vec::new();
+        self.intersect_into(ray, &mut Vec::new(), &mut hits);
+        hits.into_iter().collect()
+    }
+
+    fn intersect_into<'a>(
+        &'a self,
+        ray: Ray,
+        parents: &mut Vec<&'a dyn GenericTransform<f32, 3>>,
+        hits: &mut Vec<(f32, HitContext<'a>)>,
+    ) {
+        for &i in self.unbounded.iter() {
+            self.objects[i as usize].intersect_into(ray, parents, hits);
+        }
+        self.hierarchy
+            .traverse(&ray, f32::NEG_INFINITY, f32::INFINITY, |i| {
+                self.objects[i as usize].intersect_into(ray, parents, hits);
+                f32::INFINITY
+            });
+    }
+
+    /// Hits record the object hit, and get their normals from
+    /// [`HitContext::normal_at`] without searching for the object.
+    fn normal_at(&self, point: Point) -> Unit<Vector> {
+        self.object_at(point)
+            .map_or_else(Vector::z_axis, |o| o.normal_at(point))
+    }
+
+    fn normal_at_hit(&self, point: Point, data: &HitData) -> Unit<Vector> {
+        self.object_at(point)
+            .map_or_else(Vector::z_axis, |o| o.normal_at_hit(point, data))
+    }
+
+    fn includes(&self, other: &dyn Hittable) -> bool {
+        self.id == other.id() || self.objects.iter().any(|o| o.includes(other))
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.unbounded
+            .iter()
+            .fold(self.hierarchy.bounds(), |b, &i| {
+                b.union(&self.objects[i as usize].bounds())
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::bvh::Bvh;
+    use crate::geometry::cube::Cube;
+    use crate::geometry::plane::Plane;
+    use crate::geometry::ray::Ray;
+    use crate::geometry::sphere::Sphere;
+    use crate::geometry::triangle::Triangle;
+    use crate::geometry::{Hittable, Shape};
+    use crate::prelude::*;
+    use approx::assert_abs_diff_eq;
+    use nalgebra::point;
+
+    // a deterministic xorshift generator of floats in [0, 1)
+    fn random(mut state: u32) -> impl FnMut() -> f32 {
+        move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state >> 8) as f32 / (1 << 24) as f32
+        }
+    }
+
+    fn scene(n: usize) -> Vec<Box<dyn Hittable>> {
+        let mut rand = random(0x9e37_79b9);
+        let mut point = || {
+            point![
+                rand() * 20.0 - 10.0,
+                rand() * 20.0 - 10.0,
+                rand() * 20.0 - 10.0
+            ]
+        };
+        (0..n)
+            .map(|i| -> Box<dyn Hittable> {
+                let p = point();
+                match i % 3 {
+                    0 => Box::new(Sphere::new(p.into(), 0.3).into_object()),
+                    1 => Box::new(
+                        Cube.into_object()
+                            .scaled(0.2, 0.4, 0.3)
+                            .rotated_y(p.x)
+                            .translated(p.x, p.y, p.z),
+                    ),
+                    _ => Box::new(Triangle::new(p, p + Vector::x(), p + Vector::y()).into_object()),
+                }
+            })
+            .collect()
+    }
+
+    fn rays(n: usize) -> Vec<Ray> {
+        let mut rand = random(0x85eb_ca6b);
+        (0..n)
+            .map(|_| {
+                let orig = [rand() * 30.0 - 15.0, rand() * 30.0 - 15.0, -20.0];
+                let to = [
+                    rand() * 20.0 - 10.0,
+                    rand() * 20.0 - 10.0,
+                    rand() * 20.0 - 10.0,
+                ];
+                Ray::from_points(orig, to)
+            })
+            .collect()
+    }
+
+    fn brute_force_hit(
+        objects: &[Box<dyn Hittable>],
+        ray: Ray,
+    ) -> Option<(f32, crate::geometry::Id)> {
+        objects
+            .iter()
+            .filter_map(|o| o.intersect(ray).hit().map(|(t, ctx)| (t, ctx.obj_hit.id())))
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+    }
+
+    #[test]
+    fn test_hit_matches_brute_force() {
+        let bvh = Bvh::new(scene(300));
+        let mut hits = 0;
+        for ray in rays(300) {
+            let expected = brute_force_hit(bvh.objects(), ray);
+            let actual = bvh.hit(ray).map(|(t, ctx)| (t, ctx.obj_hit.id()));
+            assert_eq!(actual, expected);
+            hits += expected.is_some() as usize;
+        }
+        // enough of the rays hit something for the comparison to mean anything
+        assert!(hits > 50, "{} hits", hits);
+    }
+
+    #[test]
+    fn test_intersect_matches_brute_force() {
+        let bvh = Bvh::new(scene(200));
+        for ray in rays(100) {
+            let mut expected = bvh
+                .objects()
+                .iter()
+                .flat_map(|o| {
+                    o.intersect(ray)
+                        .all()
+                        .map(|(t, ctx)| (t, ctx.obj_hit.id()))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let inter = bvh.intersect(ray);
+            let mut actual = inter
+                .all()
+                .map(|(t, ctx)| (t, ctx.obj_hit.id()))
+                .collect::<Vec<_>>();
+            actual.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_any_hit_matches_brute_force() {
+        let bvh = Bvh::new(scene(200));
+        for ray in rays(100) {
+            for &t_max in [5.0, 20.0, f32::INFINITY].iter() {
+                let expected = bvh
+                    .objects()
+                    .iter()
+                    .any(|o| o.intersect(ray).between(0.0..t_max).next().is_some());
+                assert_eq!(bvh.any_hit(ray, t_max), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unbounded_and_empty() {
+        let empty: Bvh = Bvh::new(Vec::new());
+        let r = Ray::new([0.0, 1.0, 0.0], [0.0, -1.0, 0.0]);
+        assert!(empty.hit(r).is_none());
+        assert!(!empty.any_hit(r, f32::INFINITY));
+        assert!(empty.bounds().is_empty());
+        assert_eq!(empty.normal_at(point![0.0, 0.0, 0.0]).norm(), 1.0);
+
+        let floor = Plane::default().into_object().translated(0.0, -1.0, 0.0);
+        let floor_id = floor.id();
+        let objects: Vec<Box<dyn Hittable>> = vec![
+            Box::new(floor),
+            Box::new(Sphere::default().into_object().translated(5.0, 0.0, 0.0)),
+        ];
+        let bvh = objects.into_iter().collect::<Bvh>();
+        assert_eq!(bvh.bounds(), crate::geometry::bounds::Bounds::infinite());
+        let (t, ctx) = bvh.hit(r).unwrap();
+        assert_abs_diff_eq!(t, 2.0);
+        assert_eq!(ctx.obj_hit.id(), floor_id);
+        assert!(bvh.any_hit(r, 2.5));
+        assert!(!bvh.any_hit(r, 1.5));
+
+        let r = Ray::new([5.0, 5.0, 0.0], [0.0, -1.0, 0.0]);
+        assert_abs_diff_eq!(bvh.hit(r).unwrap().0, 4.0);
+        assert_eq!(bvh.intersect(r).size(), 3);
+    }
+
+    #[test]
+    fn test_normals_and_includes() {
+        let spheres = (0..20)
+            .map(|i| {
+                Sphere::default()
+                    .into_object()
+                    .translated(3.0 * i as f32, 0.0, 0.0)
+            })
+            .collect::<Bvh<_>>();
+        let p = point![30.0, 1.0, 0.0];
+        assert_abs_diff_eq!(
+            spheres.normal_at(p).into_inner(),
+            Vector::y(),
+            epsilon = 1e-6
+        );
+        // off every surface, the nearest sphere is the one at x = 30
+        assert_abs_diff_eq!(
+            spheres.normal_at(point![30.0, 1.5, 0.0]).into_inner(),
+            Vector::y(),
+            epsilon = 1e-6
+        );
+        let r = Ray::new([30.0, 0.0, 5.0], [0.0, 0.0, -1.0]);
+        let inter = spheres.intersect(r);
+        let (t, ctx) = inter.hit().unwrap();
+        assert_eq!(ctx.obj_hit, &spheres.objects()[10] as &dyn Hittable);
+        assert!(spheres.includes(ctx.obj_hit));
+        assert_abs_diff_eq!(
+            ctx.normal_at(r.trace(t)).into_inner(),
+            Vector::z(),
+            epsilon = 1e-6
+        );
+        let bounds = spheres.bounds();
+        assert_abs_diff_eq!(bounds.min, point![-1.0, -1.0, -1.0], epsilon = 1e-3);
+        assert_abs_diff_eq!(bounds.max, point![58.0, 1.0, 1.0], epsilon = 1e-3);
+    }
+}
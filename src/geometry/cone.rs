@@ -0,0 +1,171 @@
+use crate::geometry::bounds::Bounds;
+use crate::geometry::cylinder::{cap_normal, intersect_caps, EPSILON};
+use crate::geometry::ray::Ray;
+use crate::geometry::Shape;
+use crate::prelude::*;
+use arrayvec::ArrayVec;
+use nalgebra::Unit;
+
+/// The double-napped cone `x² + z² = y²` with its apex at the origin, truncated to
+/// `min < y < max` and capped at both ends when `closed`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Cone {
+    pub min: f32,
+    pub max: f32,
+    pub closed: bool,
+}
+
+impl Cone {
+    pub fn new(min: f32, max: f32, closed: bool) -> Self {
+        Self { min, max, closed }
+    }
+}
+
+/// An infinite, open cone.
+impl Default for Cone {
+    fn default() -> Self {
+        Cone::new(f32::NEG_INFINITY, f32::INFINITY, false)
+    }
+}
+
+impl Shape for Cone {
+    type Hits = ArrayVec<f32, 4>;
+
+    fn intersect(&self, ray: Ray) -> Self::Hits {
+        let Ray { orig, dir } = ray;
+        let mut hits = ArrayVec::new();
+
+        let a = dir.x.powi(2) - dir.y.powi(2) + dir.z.powi(2);
+        let b = 2.0 * (orig.x * dir.x - orig.y * dir.y + orig.z * dir.z);
+        let c = orig.x.powi(2) - orig.y.powi(2) + orig.z.powi(2);
+        let mut in_range = |t: f32| {
+            let y = orig.y + t * dir.y;
+            if self.min < y && y < self.max {
+                hits.push(t);
+            }
+        };
+        // `dir` may not be a unit vector once the ray is transformed, so
+        // tolerances scale with it
+        if a.abs() > EPSILON * dir.norm_squared() {
+            // rays grazing the cone, or through its apex, give a discriminant
+            // that is zero up to rounding
+            let delta = b.powi(2) - 4.0 * a * c;
+            if delta >= -EPSILON * b.powi(2) {
+                let delta_sqrt = delta.max(0.0).sqrt();
+                in_range((-b - delta_sqrt) / (2.0 * a));
+                in_range((-b + delta_sqrt) / (2.0 * a));
+            }
+        } else if b.abs() > EPSILON * dir.norm() {
+            // a ray parallel to one of the halves only crosses the other one
+            in_range(-c / (2.0 * b));
+        }
+
+        if self.closed {
+            intersect_caps(ray, self.min, self.max, f32::abs, &mut hits);
+        }
+        hits
+    }
+
+    fn normal_at(&self, point: Point) -> Unit<Vector> {
+        cap_normal(point, self.min, self.max, point.y.abs()).unwrap_or_else(|| {
+            let mut y = (point.x.powi(2) + point.z.powi(2)).sqrt();
+            if point.y > 0.0 {
+                y = -y;
+            }
+            Unit::new_normalize(Vector::new(point.x, y, point.z))
+        })
+    }
+
+    fn bounds(&self) -> Bounds {
+        let r = self.min.abs().max(self.max.abs());
+        Bounds::new([-r, self.min, -r], [r, self.max, r])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::cone::Cone;
+    use crate::geometry::ray::Ray;
+    use crate::geometry::tests::sorted_hits;
+    use crate::geometry::{Hittable, Shape};
+    use approx::assert_abs_diff_eq;
+    use nalgebra::{point, vector};
+
+    #[test]
+    fn test_hit_side() {
+        let c = Cone::default();
+        let cases = [
+            ([0.0, 0.0, -5.0], [0.0, 0.0, 1.0], 5.0, 5.0),
+            ([0.0, 0.0, -5.0], [1.0, 1.0, 1.0], 8.66025, 8.66025),
+            ([1.0, 1.0, -5.0], [-0.5, -1.0, 1.0], 4.55006, 49.44994),
+        ];
+        for &(orig, dir, t0, t1) in cases.iter() {
+            let ts = sorted_hits(&c, orig, dir);
+            assert_abs_diff_eq!(ts[0], t0, epsilon = 1e-3);
+            assert_abs_diff_eq!(ts[ts.len() - 1], t1, epsilon = 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_parallel_to_half() {
+        let ts = sorted_hits(&Cone::default(), [0.0, 0.0, -1.0], [0.0, 1.0, 1.0]);
+        assert_eq!(ts.len(), 1);
+        assert_abs_diff_eq!(ts[0], 0.35355, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn test_caps() {
+        let c = Cone::new(-0.5, 0.5, true);
+        let cases = [
+            ([0.0, 0.0, -5.0], [0.0, 1.0, 0.0], 0),
+            ([0.0, 0.0, -0.25], [0.0, 1.0, 1.0], 2),
+            ([0.0, 0.0, -0.25], [0.0, 1.0, 0.0], 4),
+        ];
+        for &(orig, dir, count) in cases.iter() {
+            assert_eq!(sorted_hits(&c, orig, dir).len(), count);
+        }
+    }
+
+    #[test]
+    fn test_small_caps() {
+        let c = Cone::new(-1e-3, 1e-3, true);
+        assert!(sorted_hits(&c, [2e-3, 1.0, 0.0], [0.0, -1.0, 0.0]).is_empty());
+        assert_eq!(sorted_hits(&c, [5e-4, 1.0, 0.0], [0.0, -1.0, 0.0]).len(), 4);
+    }
+
+    #[test]
+    fn test_normals() {
+        let c = Cone::default();
+        assert_abs_diff_eq!(
+            c.normal_at(point![1.0, 1.0, 1.0]).into_inner(),
+            vector![1.0, -2f32.sqrt(), 1.0].normalize()
+        );
+        assert_abs_diff_eq!(
+            c.normal_at(point![-1.0, -1.0, 0.0]).into_inner(),
+            vector![-1.0, 1.0, 0.0].normalize()
+        );
+
+        let c = Cone::new(-1.0, 2.0, true);
+        assert_eq!(
+            c.normal_at(point![0.5, 2.0, 0.5]).into_inner(),
+            vector![0.0, 1.0, 0.0]
+        );
+        assert_eq!(
+            c.normal_at(point![0.2, -1.0, -0.3]).into_inner(),
+            vector![0.0, -1.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_large_scaled_cone() {
+        let c = Cone::new(-1.0, 1.0, true)
+            .into_object()
+            .scaled(1000.0, 1000.0, 1000.0);
+        let r = Ray::new([-2000.0, 500.0, 0.0], [1.0, 0.0, 0.0]);
+        let ts = c.intersect(r).all().map(|(t, _)| t).collect::<Vec<_>>();
+        assert_abs_diff_eq!(ts.as_slice(), [1500.0, 2500.0].as_ref(), epsilon = 1e-2);
+        // parallel to one half of the cone
+        let r = Ray::new([0.0, 0.0, -500.0], [0.0, 1.0, 1.0]);
+        assert_eq!(c.intersect(r).size(), 2);
+    }
+}
@@ -0,0 +1,316 @@
+use crate::geometry::bounds::Bounds;
+use crate::geometry::ray::Ray;
+use crate::geometry::{is_on_surface, HitData, Hittable, Id, Intersection};
+use crate::prelude::*;
+use itertools::Itertools;
+use nalgebra::Unit;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl Operation {
+    /// Whether a hit on the left (or right) operand bounds the combined solid,
+    /// given whether the ray is currently inside each operand.
+    fn keeps(self, left_hit: bool, in_left: bool, in_right: bool) -> bool {
+        match self {
+            Operation::Union => (left_hit && !in_right) || (!left_hit && !in_left),
+            Operation::Intersection => (left_hit && in_right) || (!left_hit && in_left),
+            Operation::Difference => (left_hit && !in_right) || (!left_hit && in_left),
+        }
+    }
+}
+
+/// A solid built from two others. Hits report the primitive that was hit, so
+/// normals come from the operands themselves.
+#[derive(Debug, Clone)]
+pub struct Csg<L, R> {
+    id: Id,
+    operation: Operation,
+    left: L,
+    right: R,
+}
+
+impl<L: Hittable, R: Hittable> Csg<L, R> {
+    pub fn new(operation: Operation, left: L, right: R) -> Self {
+        Self {
+            id: Id::new(),
+            operation,
+            left,
+            right,
+        }
+    }
+
+    pub fn union(left: L, right: R) -> Self {
+        Csg::new(Operation::Union, left, right)
+    }
+
+    pub fn intersection(left: L, right: R) -> Self {
+        Csg::new(Operation::Intersection, left, right)
+    }
+
+    /// The left solid with the right one carved out of it.
+    pub fn difference(left: L, right: R) -> Self {
+        Csg::new(Operation::Difference, left, right)
+    }
+
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    pub fn left(&self) -> &L {
+        &self.left
+    }
+
+    pub fn right(&self) -> &R {
+        &self.right
+    }
+
+    // the normal of the combined solid where the right operand bounds it, which
+    // faces into that operand when it is carved out
+    fn right_normal(&self, normal: Unit<Vector>) -> Unit<Vector> {
+        match self.operation {
+            Operation::Difference => -normal,
+            _ => normal,
+        }
+    }
+}
+
+impl<L: Hittable, R: Hittable> Hittable for Csg<L, R> {
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn intersect(&self, ray: Ray) -> Intersection<'_> {
+        let (left, right) = (self.left.intersect(ray), self.right.intersect(ray));
+        let carved = self.operation == Operation::Difference;
+        // both sides are already ordered by distance, merging keeps them so
+        let hits = left
+            .all()
+            .map(|(t, ctx)| (t, ctx, true))
+            .merge_by(right.all().map(|(t, ctx)| (t, ctx, false)), |a, b| {
+                a.0 <= b.0
+            });
+
+        let (mut in_left, mut in_right) = (false, false);
+        hits.filter(|&(_, _, left_hit)| {
+            let keep = self.operation.keeps(left_hit, in_left, in_right);
+            if left_hit {
+                in_left = !in_left;
+            } else {
+                in_right = !in_right;
+            }
+            keep
+        })
+        .map(|(t, ctx, left_hit)| {
+            let mut ctx = ctx.clone();
+            ctx.flipped ^= carved && !left_hit;
+            (t, ctx)
+        })
+        .collect()
+    }
+
+    fn normal_at(&self, point: Point) -> Unit<Vector> {
+        if is_on_surface(&self.left, point) || !is_on_surface(&self.right, point) {
+            self.left.normal_at(point)
+        } else {
+            self.right_normal(self.right.normal_at(point))
+        }
+    }
+
+    fn normal_at_hit(&self, point: Point, data: &HitData) -> Unit<Vector> {
+        if is_on_surface(&self.left, point) || !is_on_surface(&self.right, point) {
+            self.left.normal_at_hit(point, data)
+        } else {
+            self.right_normal(self.right.normal_at_hit(point, data))
+        }
+    }
+
+    fn includes(&self, other: &dyn Hittable) -> bool {
+        self.id == other.id() || self.left.includes(other) || self.right.includes(other)
+    }
+
+    fn bounds(&self) -> Bounds {
+        let (left, right) = (self.left.bounds(), self.right.bounds());
+        match self.operation {
+            Operation::Union => left.union(&right),
+            Operation::Intersection => left.intersection(&right),
+            Operation::Difference => left,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::bounds::Bounds;
+    use crate::geometry::csg::{Csg, Operation};
+    use crate::geometry::cube::Cube;
+    use crate::geometry::cylinder::Cylinder;
+    use crate::geometry::ray::Ray;
+    use crate::geometry::sphere::Sphere;
+    use crate::geometry::{Hittable, Shape};
+    use crate::prelude::*;
+    use approx::assert_abs_diff_eq;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn ts(h: &dyn Hittable, ray: Ray) -> Vec<f32> {
+        h.intersect(ray).all().map(|(t, _)| t).collect()
+    }
+
+    #[test]
+    fn test_rules() {
+        use Operation::*;
+        let table = [
+            (Union, [true, true, true], false),
+            (Union, [true, true, false], true),
+            (Union, [true, false, true], false),
+            (Union, [true, false, false], true),
+            (Union, [false, true, true], false),
+            (Union, [false, true, false], false),
+            (Union, [false, false, true], true),
+            (Union, [false, false, false], true),
+            (Intersection, [true, true, true], true),
+            (Intersection, [true, true, false], false),
+            (Intersection, [true, false, true], true),
+            (Intersection, [true, false, false], false),
+            (Intersection, [false, true, true], true),
+            (Intersection, [false, true, false], true),
+            (Intersection, [false, false, true], false),
+            (Intersection, [false, false, false], false),
+            (Difference, [true, true, true], false),
+            (Difference, [true, true, false], true),
+            (Difference, [true, false, true], false),
+            (Difference, [true, false, false], true),
+            (Difference, [false, true, true], true),
+            (Difference, [false, true, false], true),
+            (Difference, [false, false, true], false),
+            (Difference, [false, false, false], false),
+        ];
+        for &(op, [left_hit, in_left, in_right], expected) in table.iter() {
+            assert_eq!(op.keeps(left_hit, in_left, in_right), expected);
+        }
+    }
+
+    #[test]
+    fn test_filtering() {
+        let ray = Ray::new([0.0, 0.0, -5.0], [0.0, 0.0, 1.0]);
+        let left = || Sphere::default().into_object();
+        let right = || Cube.into_object().translated(0.0, 0.0, 0.5);
+        // the sphere spans 4..6 and the box 4.5..6.5
+        assert_eq!(ts(&Csg::union(left(), right()), ray), [4.0, 6.5]);
+        assert_eq!(ts(&Csg::intersection(left(), right()), ray), [4.5, 6.0]);
+        assert_eq!(ts(&Csg::difference(left(), right()), ray), [4.0, 4.5]);
+    }
+
+    #[test]
+    fn test_miss() {
+        let c = Csg::union(Sphere::default().into_object(), Cube.into_object());
+        let r = Ray::new([0.0, 2.0, -5.0], [0.0, 0.0, 1.0]);
+        assert_eq!(c.intersect(r).size(), 0);
+    }
+
+    #[test]
+    fn test_includes() {
+        let (a, b) = (Sphere::default().into_object(), Cube.into_object());
+        let (a_id, b_id) = (a.id(), b.id());
+        assert_ne!(a_id, b_id);
+        let c = Csg::difference(
+            Box::new(a) as Box<dyn Hittable>,
+            Box::new(b) as Box<dyn Hittable>,
+        );
+        let outer = Csg::union(c, Sphere::default().into_object().translated(3.0, 0.0, 0.0));
+        let inner = outer.left();
+        assert!(inner.includes(&**inner.left()));
+        assert!(inner.includes(&**inner.right()));
+        assert!(outer.includes(&**inner.right()));
+        assert!(outer.includes(inner));
+        assert!(!inner.includes(outer.right()));
+    }
+
+    #[test]
+    fn test_nested() {
+        // a sphere with a box carved out of it, unioned with a second sphere
+        let carved = Csg::difference(
+            Sphere::default().into_object(),
+            Cube.into_object().translated(0.0, 0.0, -1.5),
+        );
+        let c = Csg::union(
+            carved,
+            Sphere::default().into_object().translated(0.0, 0.0, 1.5),
+        );
+        let ray = Ray::new([0.0, 0.0, -5.0], [0.0, 0.0, 1.0]);
+        // the box removes the front of the first sphere, up to z = -0.5
+        assert_eq!(ts(&c, ray), [4.5, 7.5]);
+    }
+
+    #[test]
+    fn test_hole_in_box() {
+        let drill = Cylinder::new(-2.0, 2.0, true)
+            .into_object()
+            .scaled(0.5, 1.0, 0.5)
+            .rotated_x(FRAC_PI_2);
+        let block = Csg::difference(Cube.into_object(), drill);
+
+        let through_hole = Ray::new([0.0, 0.0, -5.0], [0.0, 0.0, 1.0]);
+        assert_eq!(block.intersect(through_hole).size(), 0);
+
+        let beside_hole = Ray::new([0.75, 0.0, -5.0], [0.0, 0.0, 1.0]);
+        assert_eq!(ts(&block, beside_hole), [4.0, 6.0]);
+
+        // across the hole, the walls face into it, away from the solid
+        let across = Ray::new([-5.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        let inter = block.intersect(across);
+        let walls = inter.all().map(|(t, _)| t).collect::<Vec<_>>();
+        assert_eq!(walls.len(), 4);
+        assert_abs_diff_eq!(walls[1], 4.5, epsilon = 1e-5);
+        let (t, ctx) = inter.all().nth(1).unwrap();
+        assert_abs_diff_eq!(
+            ctx.normal_at(across.trace(t)).into_inner(),
+            Vector::x(),
+            epsilon = 1e-5
+        );
+        assert_abs_diff_eq!(
+            block.normal_at(across.trace(t)).into_inner(),
+            Vector::x(),
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn test_lens() {
+        let lens = Csg::intersection(
+            Sphere::default().into_object().translated(0.0, 0.0, -0.8),
+            Sphere::default().into_object().translated(0.0, 0.0, 0.8),
+        );
+        let r = Ray::new([0.0, 0.0, -5.0], [0.0, 0.0, 1.0]);
+        let ts = ts(&lens, r);
+        assert_abs_diff_eq!(ts[0], 4.8, epsilon = 1e-5);
+        assert_abs_diff_eq!(ts[1], 5.2, epsilon = 1e-5);
+        // the front face of the lens is the back sphere's surface
+        let n = lens.normal_at(r.trace(ts[0]));
+        assert_abs_diff_eq!(n.into_inner(), -Vector::z(), epsilon = 1e-5);
+        let r = Ray::new([0.0, 0.7, -5.0], [0.0, 0.0, 1.0]);
+        assert_eq!(lens.intersect(r).size(), 0);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let left = || Sphere::default().into_object();
+        let right = || Cube.into_object().translated(1.0, 0.0, 0.0);
+        assert_eq!(
+            Csg::union(left(), right()).bounds(),
+            Bounds::new([-1.0, -1.0, -1.0], [2.0, 1.0, 1.0])
+        );
+        assert_eq!(
+            Csg::intersection(left(), right()).bounds(),
+            Bounds::new([0.0, -1.0, -1.0], [1.0, 1.0, 1.0])
+        );
+        assert_eq!(
+            Csg::difference(left(), right()).bounds(),
+            Bounds::new([-1.0, -1.0, -1.0], [1.0, 1.0, 1.0])
+        );
+    }
+}
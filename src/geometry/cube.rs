@@ -0,0 +1,192 @@
+use crate::geometry::bounds::Bounds;
+use crate::geometry::ray::Ray;
+use crate::geometry::Shape;
+use crate::prelude::*;
+use arrayvec::ArrayVec;
+use nalgebra::Unit;
+
+/// An axis-aligned box spanning `min` to `max`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Aabb {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Aabb {
+    pub fn new(min: [f32; 3], max: [f32; 3]) -> Self {
+        Self {
+            min: min.into(),
+            max: max.into(),
+        }
+    }
+
+    pub fn center(&self) -> Point {
+        nalgebra::center(&self.min, &self.max)
+    }
+}
+
+impl Shape for Aabb {
+    type Hits = ArrayVec<f32, 2>;
+
+    fn intersect(&self, ray: Ray) -> Self::Hits {
+        let mut solution = ArrayVec::new();
+        if let Some((t_min, t_max)) = self.bounds().intersect(&ray) {
+            solution.push(t_min);
+            if t_min < t_max {
+                solution.push(t_max);
+            }
+        }
+        solution
+    }
+
+    /// The normal of the face closest to `point`. On edges and corners, where faces
+    /// meet, the x face wins over y and y over z.
+    fn normal_at(&self, point: Point) -> Unit<Vector> {
+        let half = (self.max - self.min) / 2.0;
+        let local = (point - self.center()).component_div(&half);
+        let axis = local.iamax();
+        let mut normal = Vector::zeros();
+        normal[axis] = local[axis].signum();
+        Unit::new_unchecked(normal)
+    }
+
+    fn bounds(&self) -> Bounds {
+        Bounds {
+            min: self.min,
+            max: self.max,
+        }
+    }
+}
+
+/// The box from `(-1, -1, -1)` to `(1, 1, 1)`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Cube;
+
+impl Cube {
+    fn aabb() -> Aabb {
+        Aabb::new([-1.0, -1.0, -1.0], [1.0, 1.0, 1.0])
+    }
+}
+
+impl Shape for Cube {
+    type Hits = ArrayVec<f32, 2>;
+
+    fn intersect(&self, ray: Ray) -> Self::Hits {
+        Cube::aabb().intersect(ray)
+    }
+
+    fn normal_at(&self, point: Point) -> Unit<Vector> {
+        Cube::aabb().normal_at(point)
+    }
+
+    fn bounds(&self) -> Bounds {
+        Cube::aabb().bounds()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::cube::{Aabb, Cube};
+    use crate::geometry::ray::Ray;
+    use crate::geometry::{Hittable, Shape};
+    use crate::prelude::*;
+    use approx::assert_abs_diff_eq;
+    use nalgebra::{point, vector};
+    use std::f32::consts::FRAC_PI_4;
+
+    #[test]
+    fn test_intersect_faces() {
+        let cases = [
+            ([5.0, 0.5, 0.0], [-1.0, 0.0, 0.0], [4.0, 6.0]),
+            ([-5.0, 0.5, 0.0], [1.0, 0.0, 0.0], [4.0, 6.0]),
+            ([0.5, 5.0, 0.0], [0.0, -1.0, 0.0], [4.0, 6.0]),
+            ([0.5, -5.0, 0.0], [0.0, 1.0, 0.0], [4.0, 6.0]),
+            ([0.5, 0.0, 5.0], [0.0, 0.0, -1.0], [4.0, 6.0]),
+            ([0.5, 0.0, -5.0], [0.0, 0.0, 1.0], [4.0, 6.0]),
+            ([0.0, 0.5, 0.0], [0.0, 0.0, 1.0], [-1.0, 1.0]),
+        ];
+        for &(orig, dir, expected) in cases.iter() {
+            assert_eq!(Cube.intersect(Ray::new(orig, dir)).as_slice(), expected);
+        }
+    }
+
+    #[test]
+    fn test_miss() {
+        let cases = [
+            ([-2.0, 0.0, 0.0], [0.2673, 0.5345, 0.8018]),
+            ([0.0, -2.0, 0.0], [0.8018, 0.2673, 0.5345]),
+            ([0.0, 0.0, -2.0], [0.5345, 0.8018, 0.2673]),
+            ([2.0, 0.0, 2.0], [0.0, 0.0, -1.0]),
+            ([0.0, 2.0, 2.0], [0.0, -1.0, 0.0]),
+            ([2.0, 2.0, 0.0], [-1.0, 0.0, 0.0]),
+        ];
+        for &(orig, dir) in cases.iter() {
+            assert!(Cube.intersect(Ray::new(orig, dir)).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_normals() {
+        let cases = [
+            (point![1.0, 0.5, -0.8], vector![1.0, 0.0, 0.0]),
+            (point![-1.0, -0.2, 0.9], vector![-1.0, 0.0, 0.0]),
+            (point![-0.4, 1.0, -0.1], vector![0.0, 1.0, 0.0]),
+            (point![0.3, -1.0, -0.7], vector![0.0, -1.0, 0.0]),
+            (point![-0.6, 0.3, 1.0], vector![0.0, 0.0, 1.0]),
+            (point![0.4, 0.4, -1.0], vector![0.0, 0.0, -1.0]),
+            (point![1.0, 1.0, 1.0], vector![1.0, 0.0, 0.0]),
+            (point![-1.0, -1.0, -1.0], vector![-1.0, 0.0, 0.0]),
+            (point![0.2, 1.0, 1.0], vector![0.0, 1.0, 0.0]),
+        ];
+        for &(p, n) in cases.iter() {
+            assert_eq!(Cube.normal_at(p).into_inner(), n);
+        }
+    }
+
+    #[test]
+    fn test_general_box() {
+        let b = Aabb::new([1.0, -2.0, 0.0], [3.0, 2.0, 10.0]);
+        let r = Ray::new([2.0, 0.0, -5.0], [0.0, 0.0, 1.0]);
+        assert_eq!(b.intersect(r).as_slice(), [5.0, 15.0]);
+        assert_eq!(b.normal_at(point![2.0, 0.0, 0.0]), -Vector::z_axis());
+        assert_eq!(b.normal_at(point![3.0, 1.0, 4.0]), Vector::x_axis());
+        assert_eq!(b.normal_at(point![2.5, -2.0, 9.0]), -Vector::y_axis());
+    }
+
+    #[test]
+    fn test_inside_outside_queries() {
+        let room = Cube.into_object().scaled(5.0, 3.0, 5.0);
+        let r = Ray::new([0.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        let inter = room.intersect(r);
+        // an origin inside the box has one hit behind it and one ahead
+        assert_eq!(inter.between(..0.0).count(), 1);
+        assert_abs_diff_eq!(inter.hit().unwrap().0, 5.0);
+
+        let r = Ray::new([-10.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        let ts = room
+            .intersect(r)
+            .between(0.0..)
+            .map(|(t, _)| t)
+            .collect::<Vec<_>>();
+        assert_eq!(ts, [5.0, 15.0]);
+    }
+
+    #[test]
+    fn test_huge_box() {
+        let b = Cube.into_object().scaled(1e10, 1e10, 1e10);
+        let r = Ray::new([-2e10, 5e9, 0.0], [1.0, 0.0, 0.0]);
+        let ts = b.intersect(r).all().map(|(t, _)| t).collect::<Vec<_>>();
+        assert_abs_diff_eq!(ts.as_slice(), [1e10, 3e10].as_ref(), epsilon = 1e4);
+    }
+
+    #[test]
+    fn test_rotated_box_normal() {
+        let table = Cube.into_object().rotated_y(FRAC_PI_4);
+        let r = Ray::new([0.0, 0.0, -5.0], [0.0, 0.0, 1.0]);
+        let (t, _) = table.intersect(r).hit().unwrap();
+        assert_abs_diff_eq!(t, 5.0 - 2f32.sqrt(), epsilon = 1e-5);
+        // hits the edge between two faces; either neighbour faces the ray
+        let n = table.normal_at(r.trace(t));
+        assert_abs_diff_eq!(n.z, -(0.5f32).sqrt(), epsilon = 1e-5);
+    }
+}
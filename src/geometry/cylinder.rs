@@ -0,0 +1,241 @@
+use crate::geometry::bounds::Bounds;
+use crate::geometry::ray::Ray;
+use crate::geometry::Shape;
+use crate::prelude::*;
+use arrayvec::ArrayVec;
+use nalgebra::Unit;
+
+pub(super) const EPSILON: f32 = 1e-5;
+
+/// A cylinder of radius 1 around the y axis, truncated to `min < y < max` and
+/// capped at both ends when `closed`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Cylinder {
+    pub min: f32,
+    pub max: f32,
+    pub closed: bool,
+}
+
+impl Cylinder {
+    pub fn new(min: f32, max: f32, closed: bool) -> Self {
+        Self { min, max, closed }
+    }
+}
+
+/// An infinite, open cylinder.
+impl Default for Cylinder {
+    fn default() -> Self {
+        Cylinder::new(f32::NEG_INFINITY, f32::INFINITY, false)
+    }
+}
+
+impl Shape for Cylinder {
+    type Hits = ArrayVec<f32, 4>;
+
+    fn intersect(&self, ray: Ray) -> Self::Hits {
+        let Ray { orig, dir } = ray;
+        let mut hits = ArrayVec::new();
+
+        let a = dir.x.powi(2) + dir.z.powi(2);
+        // rays parallel to the y axis can only hit the caps. `dir` may not be a
+        // unit vector once the ray is transformed, so tolerances scale with it
+        if a > EPSILON * dir.norm_squared() {
+            let b = 2.0 * (orig.x * dir.x + orig.z * dir.z);
+            let c = orig.x.powi(2) + orig.z.powi(2) - 1.0;
+            let delta = b.powi(2) - 4.0 * a * c;
+            if delta < 0.0 {
+                return hits;
+            }
+            let delta_sqrt = delta.sqrt();
+            for &t in [(-b - delta_sqrt) / (2.0 * a), (-b + delta_sqrt) / (2.0 * a)].iter() {
+                let y = orig.y + t * dir.y;
+                if self.min < y && y < self.max {
+                    hits.push(t);
+                }
+            }
+        }
+
+        if self.closed {
+            intersect_caps(ray, self.min, self.max, |_| 1.0, &mut hits);
+        }
+        hits
+    }
+
+    fn normal_at(&self, point: Point) -> Unit<Vector> {
+        cap_normal(point, self.min, self.max, 1.0)
+            .unwrap_or_else(|| Unit::new_normalize(Vector::new(point.x, 0.0, point.z)))
+    }
+
+    fn bounds(&self) -> Bounds {
+        Bounds::new([-1.0, self.min, -1.0], [1.0, self.max, 1.0])
+    }
+}
+
+/// Adds the hits with the disks at `y = min` and `y = max`, whose radius at height
+/// `y` is `radius(y)`.
+pub(super) fn intersect_caps(
+    Ray { orig, dir }: Ray,
+    min: f32,
+    max: f32,
+    radius: impl Fn(f32) -> f32,
+    hits: &mut ArrayVec<f32, 4>,
+) {
+    if dir.y.abs() < EPSILON * dir.norm() {
+        return;
+    }
+    for &y in [min, max].iter() {
+        let t = (y - orig.y) / dir.y;
+        let (x, z) = (orig.x + t * dir.x, orig.z + t * dir.z);
+        if x.powi(2) + z.powi(2) <= radius(y).powi(2) * (1.0 + EPSILON) {
+            hits.push(t);
+        }
+    }
+}
+
+/// The normal of the cap `point` lies on, if any.
+pub(super) fn cap_normal(point: Point, min: f32, max: f32, radius: f32) -> Option<Unit<Vector>> {
+    let inside = point.x.powi(2) + point.z.powi(2) < radius.powi(2);
+    // how far from a cap still counts as on it, relative to the size of the cap
+    let tolerance = |y: f32| EPSILON * y.abs().max(radius);
+    if inside && point.y >= max - tolerance(max) {
+        Some(Vector::y_axis())
+    } else if inside && point.y <= min + tolerance(min) {
+        Some(-Vector::y_axis())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::cylinder::Cylinder;
+    use crate::geometry::ray::Ray;
+    use crate::geometry::tests::sorted_hits;
+    use crate::geometry::{Hittable, Shape};
+    use crate::prelude::*;
+    use approx::assert_abs_diff_eq;
+    use nalgebra::{point, vector};
+
+    #[test]
+    fn test_miss() {
+        let c = Cylinder::default();
+        assert!(sorted_hits(&c, [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]).is_empty());
+        assert!(sorted_hits(&c, [0.0, 0.0, 0.0], [0.0, 1.0, 0.0]).is_empty());
+        assert!(sorted_hits(&c, [0.0, 0.0, -5.0], [1.0, 1.0, 1.0]).is_empty());
+    }
+
+    #[test]
+    fn test_hit_side() {
+        let c = Cylinder::default();
+        let cases = [
+            ([1.0, 0.0, -5.0], [0.0, 0.0, 1.0], 5.0, 5.0),
+            ([0.0, 0.0, -5.0], [0.0, 0.0, 1.0], 4.0, 6.0),
+            ([0.5, 0.0, -5.0], [0.1, 1.0, 1.0], 6.80798, 7.08872),
+        ];
+        for &(orig, dir, t0, t1) in cases.iter() {
+            let ts = sorted_hits(&c, orig, dir);
+            assert_abs_diff_eq!(ts[0], t0, epsilon = 1e-4);
+            assert_abs_diff_eq!(ts[ts.len() - 1], t1, epsilon = 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_truncated() {
+        let c = Cylinder::new(1.0, 2.0, false);
+        let cases = [
+            ([0.0, 1.5, 0.0], [0.1, 1.0, 0.0], 0),
+            ([0.0, 3.0, -5.0], [0.0, 0.0, 1.0], 0),
+            ([0.0, 0.0, -5.0], [0.0, 0.0, 1.0], 0),
+            ([0.0, 2.0, -5.0], [0.0, 0.0, 1.0], 0),
+            ([0.0, 1.0, -5.0], [0.0, 0.0, 1.0], 0),
+            ([0.0, 1.5, -2.0], [0.0, 0.0, 1.0], 2),
+        ];
+        for &(orig, dir, count) in cases.iter() {
+            assert_eq!(sorted_hits(&c, orig, dir).len(), count);
+        }
+    }
+
+    #[test]
+    fn test_caps() {
+        let c = Cylinder::new(1.0, 2.0, true);
+        let cases = [
+            ([0.0, 3.0, 0.0], [0.0, -1.0, 0.0], 2),
+            ([0.0, 3.0, -2.0], [0.0, -1.0, 2.0], 2),
+            ([0.0, 4.0, -2.0], [0.0, -1.0, 1.0], 2),
+            ([0.0, 0.0, -2.0], [0.0, 1.0, 2.0], 2),
+            ([0.0, -1.0, -2.0], [0.0, 1.0, 1.0], 2),
+        ];
+        for &(orig, dir, count) in cases.iter() {
+            assert_eq!(sorted_hits(&c, orig, dir).len(), count);
+        }
+        assert_eq!(
+            sorted_hits(&c, [0.0, 3.0, 0.0], [0.0, -1.0, 0.0]),
+            [1.0, 2.0]
+        );
+    }
+
+    #[test]
+    fn test_normals() {
+        let c = Cylinder::default();
+        assert_eq!(c.normal_at(point![1.0, 0.0, 0.0]), Vector::x_axis());
+        assert_eq!(c.normal_at(point![0.0, 5.0, -1.0]), -Vector::z_axis());
+        assert_eq!(c.normal_at(point![0.0, -2.0, 1.0]), Vector::z_axis());
+        assert_eq!(c.normal_at(point![-1.0, 1.0, 0.0]), -Vector::x_axis());
+
+        let c = Cylinder::new(1.0, 2.0, true);
+        let cases = [
+            (point![0.0, 1.0, 0.0], -Vector::y()),
+            (point![0.5, 1.0, 0.0], -Vector::y()),
+            (point![0.0, 1.0, 0.5], -Vector::y()),
+            (point![0.0, 2.0, 0.0], Vector::y()),
+            (point![0.5, 2.0, 0.0], Vector::y()),
+            (point![0.0, 2.0, 0.5], Vector::y()),
+            (point![1.0, 1.5, 0.0], Vector::x()),
+        ];
+        for &(p, n) in cases.iter() {
+            assert_eq!(c.normal_at(p).into_inner(), n);
+        }
+    }
+
+    #[test]
+    fn test_pillar() {
+        let pillar = Cylinder::new(0.0, 1.0, true)
+            .into_object()
+            .scaled(0.5, 3.0, 0.5);
+        let r = Ray::new([0.0, 5.0, 0.0], [0.0, -1.0, 0.0]);
+        let (t, _) = pillar.intersect(r).hit().unwrap();
+        assert_abs_diff_eq!(t, 2.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(pillar.normal_at(r.trace(t)).into_inner(), Vector::y());
+
+        let r = Ray::new([-5.0, 1.0, 0.0], [1.0, 0.0, 0.0]);
+        let (t, _) = pillar.intersect(r).hit().unwrap();
+        assert_abs_diff_eq!(t, 4.5, epsilon = 1e-5);
+        assert_abs_diff_eq!(
+            pillar.normal_at(r.trace(t)).into_inner(),
+            vector![-1.0, 0.0, 0.0],
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_large_scaled_cylinder() {
+        // the local direction of a ray shrinks with the scale
+        let c = Cylinder::new(-1.0, 1.0, true)
+            .into_object()
+            .scaled(1000.0, 1.0, 1000.0);
+        let r = Ray::new([-2000.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        let ts = c.intersect(r).all().map(|(t, _)| t).collect::<Vec<_>>();
+        assert_abs_diff_eq!(ts.as_slice(), [1000.0, 3000.0].as_ref(), epsilon = 1e-2);
+        let r = Ray::new([0.0, 5.0, 0.0], [0.0, -1.0, 0.0]);
+        assert_eq!(c.intersect(r).size(), 2);
+    }
+
+    #[test]
+    fn test_far_cap_normal() {
+        // points computed on a cap far from the origin are off by more than an
+        // absolute tolerance
+        let c = Cylinder::new(999.0, 1000.0, true);
+        assert_eq!(c.normal_at(point![0.5, 999.9999, 0.0]), Vector::y_axis());
+        assert_eq!(c.normal_at(point![0.5, 999.0001, 0.0]), -Vector::y_axis());
+    }
+}
@@ -0,0 +1,316 @@
+use crate::geometry::bounds::Bounds;
+use crate::geometry::ray::Ray;
+use crate::geometry::{
+    is_on_surface, normal_to_world, HitContext, HitData, Hittable, Id, Intersection, Object,
+};
+use crate::prelude::*;
+use crate::util::transform::GenericTransform;
+use nalgebra::Unit;
+use std::iter::FromIterator;
+
+/// Hittables placed together. Wrapped in an [`Object`], the object's transform
+/// applies on top of each child's own transform.
+#[derive(Debug, Default)]
+pub struct Group {
+    children: Vec<Box<dyn Hittable>>,
+}
+
+impl Group {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_child(mut self, child: impl Hittable + 'static) -> Self {
+        self.push(child);
+        self
+    }
+
+    pub fn push(&mut self, child: impl Hittable + 'static) {
+        self.children.push(Box::new(child));
+    }
+
+    pub fn children(&self) -> &[Box<dyn Hittable>] {
+        &self.children
+    }
+
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// The union of the children's bounds, in group space.
+    pub fn bounds(&self) -> Bounds {
+        self.children.iter().fold(Bounds::empty(), |bounds, child| {
+            bounds.union(&child.bounds())
+        })
+    }
+
+    pub fn into_object(self) -> Object<Self> {
+        Object::new(self)
+    }
+
+    // the child whose surface passes through a point in group space, or else the
+    // one with the nearest bounds, skipping children with nothing to hit, such as
+    // empty groups
+    fn child_at(&self, point: Point) -> Option<&dyn Hittable> {
+        let solid = self
+            .children
+            .iter()
+            .map(|child| (child, child.bounds()))
+            .filter(|(_, bounds)| !bounds.is_empty());
+        solid
+            .clone()
+            .find(|(child, _)| is_on_surface(&***child, point))
+            .or_else(|| {
+                solid.min_by(|(_, a), (_, b)| a.distance(&point).total_cmp(&b.distance(&point)))
+            })
+            .map(|(child, _)| &**child)
+    }
+}
+
+impl FromIterator<Box<dyn Hittable>> for Group {
+    fn from_iter<I: IntoIterator<Item = Box<dyn Hittable>>>(iter: I) -> Self {
+        Self {
+            children: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T: GenericTransform<f32, 3>> Hittable for Object<Group, T> {
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn intersect(&self, ray: Ray) -> Intersection<'_> {
+        let mut hits = Vec::new();
+        self.intersect_into(ray, &mut Vec::new(), &mut hits);
+        hits.into_iter().collect()
+    }
+
+    fn intersect_into<'a>(
+        &'a self,
+        ray: Ray,
+        parents: &mut Vec<&'a dyn GenericTransform<f32, 3>>,
+        hits: &mut Vec<(f32, HitContext<'a>)>,
+    ) {
+        let local = ray.transform(&self.inverse);
+        parents.push(&self.inverse);
+        for child in self.shape.children.iter() {
+            child.intersect_into(local, parents, hits);
+        }
+        parents.pop();
+    }
+
+    /// Hits record the child hit and the groups around it, and get their
+    /// normals from [`HitContext::normal_at`] without searching for the child.
+    fn normal_at(&self, point: Point) -> Unit<Vector> {
+        let local = self.inverse.transform_point(&point);
+        self.shape
+            .child_at(local)
+            .map_or_else(Vector::z_axis, |child| {
+                normal_to_world(&self.inverse, child.normal_at(local))
+            })
+    }
+
+    fn normal_at_hit(&self, point: Point, data: &HitData) -> Unit<Vector> {
+        let local = self.inverse.transform_point(&point);
+        self.shape
+            .child_at(local)
+            .map_or_else(Vector::z_axis, |child| {
+                normal_to_world(&self.inverse, child.normal_at_hit(local, data))
+            })
+    }
+
+    fn includes(&self, other: &dyn Hittable) -> bool {
+        self.id == other.id() || self.shape.children.iter().any(|c| c.includes(other))
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.shape.bounds().transform(&self.transform)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::bounds::Bounds;
+    use crate::geometry::csg::Csg;
+    use crate::geometry::cube::Cube;
+    use crate::geometry::group::Group;
+    use crate::geometry::ray::Ray;
+    use crate::geometry::sphere::Sphere;
+    use crate::geometry::{Hittable, Shape};
+    use approx::assert_abs_diff_eq;
+    use nalgebra::{point, vector};
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn test_empty() {
+        let g = Group::new().into_object();
+        let r = Ray::new([0.0, 0.0, 0.0], [0.0, 0.0, 1.0]);
+        assert_eq!(g.intersect(r).size(), 0);
+        assert_eq!(g.normal_at(point![0.0, 0.0, 0.0]).norm(), 1.0);
+    }
+
+    #[test]
+    fn test_normal_off_surface() {
+        // a point on neither surface takes the normal of the nearest child
+        let g = Group::new()
+            .with_child(Sphere::default().into_object())
+            .with_child(Cube.into_object().translated(5.0, 0.0, 0.0))
+            .into_object();
+        let n = g.normal_at(point![5.0, 1.5, 0.0]);
+        assert_eq!(n.into_inner(), vector![0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_intersect_children() {
+        let s1 = Sphere::default().into_object();
+        let s2 = Sphere::default().into_object().translated(0.0, 0.0, -3.0);
+        let s3 = Sphere::default().into_object().translated(5.0, 0.0, 0.0);
+        let (id1, id2, id3) = (s1.id(), s2.id(), s3.id());
+        let g = Group::new()
+            .with_child(s1)
+            .with_child(s2)
+            .with_child(s3)
+            .into_object();
+        assert_eq!(g.shape().len(), 3);
+
+        let r = Ray::new([0.0, 0.0, -5.0], [0.0, 0.0, 1.0]);
+        let inter = g.intersect(r);
+        let hits = inter
+            .all()
+            .map(|(t, ctx)| (t, ctx.obj_hit.id()))
+            .collect::<Vec<_>>();
+        assert_eq!(hits, [(1.0, id2), (3.0, id2), (4.0, id1), (6.0, id1)]);
+        assert!(hits.iter().all(|&(_, id)| id != id3));
+    }
+
+    #[test]
+    fn test_transformed_group() {
+        let g = Group::new()
+            .with_child(Sphere::default().into_object().translated(5.0, 0.0, 0.0))
+            .into_object()
+            .scaled(2.0, 2.0, 2.0);
+        let r = Ray::new([10.0, 0.0, -10.0], [0.0, 0.0, 1.0]);
+        assert_eq!(g.intersect(r).size(), 2);
+    }
+
+    fn nested() -> impl Hittable {
+        let inner = Group::new()
+            .with_child(Sphere::default().into_object().translated(5.0, 0.0, 0.0))
+            .into_object()
+            .scaled(1.0, 2.0, 3.0);
+        Group::new()
+            .with_child(inner)
+            .into_object()
+            .rotated_y(FRAC_PI_2)
+    }
+
+    #[test]
+    fn test_nested_normal() {
+        let g = nested();
+        let n = g.normal_at(point![1.7321, 1.1547, -5.5774]);
+        assert_abs_diff_eq!(
+            n.into_inner(),
+            vector![0.2857, 0.4286, -0.8571],
+            epsilon = 1e-4
+        );
+    }
+
+    #[test]
+    fn test_nested_hit_normal() {
+        let g = nested();
+        let p = point![1.7321, 1.1547, -5.5774];
+        let expected = vector![0.2857, 0.4286, -0.8571];
+        let r = Ray::new(p + expected * 5.0, -expected);
+        let inter = g.intersect(r);
+        let (t, ctx) = inter.hit().unwrap();
+        assert_eq!(ctx.parents.len(), 2);
+        assert_abs_diff_eq!(t, 5.0, epsilon = 1e-3);
+        assert_abs_diff_eq!(
+            ctx.normal_at(r.trace(t)).into_inner(),
+            expected,
+            epsilon = 1e-3
+        );
+    }
+
+    #[test]
+    fn test_tiny_children() {
+        // two spheres a few radii apart, far closer than a fixed probe length
+        let tiny = || Sphere::default().into_object().scaled(1e-5, 1e-5, 1e-5);
+        let g = Group::new()
+            .with_child(tiny())
+            .with_child(tiny().translated(4e-5, 0.0, 0.0))
+            .into_object();
+        let n = g.normal_at(point![4e-5, 1e-5, 0.0]);
+        assert_abs_diff_eq!(n.into_inner(), vector![0.0, 1.0, 0.0], epsilon = 1e-4);
+    }
+
+    #[test]
+    fn test_empty_nested_group() {
+        let g = Group::new()
+            .with_child(Group::new().into_object())
+            .with_child(Sphere::default().into_object().translated(0.0, 0.0, 3.0))
+            .into_object();
+        let n = g.normal_at(point![0.0, 1.0, 3.0]);
+        assert_abs_diff_eq!(n.into_inner(), vector![0.0, 1.0, 0.0], epsilon = 1e-5);
+        let r = Ray::new([0.0, 0.0, -5.0], [0.0, 0.0, 1.0]);
+        let inter = g.intersect(r);
+        let (t, ctx) = inter.hit().unwrap();
+        assert_eq!(t, 7.0);
+        assert_abs_diff_eq!(
+            ctx.normal_at(r.trace(t)).into_inner(),
+            vector![0.0, 0.0, -1.0],
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn test_includes() {
+        let s = Sphere::default().into_object();
+        let id = s.id();
+        let g = Group::new().with_child(s).into_object();
+        let outer = Group::new().with_child(g).into_object();
+        let r = Ray::new([0.0, 0.0, -5.0], [0.0, 0.0, 1.0]);
+        let inter = outer.intersect(r);
+        let (_, ctx) = inter.hit().unwrap();
+        assert_eq!(ctx.obj_hit.id(), id);
+        assert!(outer.includes(ctx.obj_hit));
+        assert!(!Sphere::default().into_object().includes(ctx.obj_hit));
+    }
+
+    #[test]
+    fn test_group_in_csg() {
+        // two boxes side by side, minus a sphere in the middle of the first
+        let boxes = Group::new()
+            .with_child(Cube.into_object())
+            .with_child(Cube.into_object().translated(3.0, 0.0, 0.0))
+            .into_object();
+        let c = Csg::difference(boxes, Sphere::default().into_object().scaled(0.5, 0.5, 0.5));
+        let r = Ray::new([-5.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        let ts = c.intersect(r).all().map(|(t, _)| t).collect::<Vec<_>>();
+        assert_eq!(ts, [4.0, 4.5, 5.5, 6.0, 7.0, 9.0]);
+    }
+
+    #[test]
+    fn test_bounds() {
+        assert!(Group::new().into_object().bounds().is_empty());
+        let g = Group::new()
+            .with_child(Sphere::new([0.0, 0.0, 0.0], 1.0).into_object())
+            .with_child(Cube.into_object().translated(5.0, 0.0, 0.0))
+            .into_object()
+            .scaled(1.0, 2.0, 1.0)
+            .translated(0.0, 0.0, 1.0);
+        assert_eq!(g.bounds(), Bounds::new([-1.0, -2.0, 0.0], [6.0, 2.0, 2.0]));
+        let outer = Group::new()
+            .with_child(g)
+            .into_object()
+            .rotated_z(FRAC_PI_2);
+        let b = outer.bounds();
+        assert_abs_diff_eq!(b.min, point![-2.0, -1.0, 0.0], epsilon = 1e-5);
+        assert_abs_diff_eq!(b.max, point![2.0, 6.0, 2.0], epsilon = 1e-5);
+    }
+}
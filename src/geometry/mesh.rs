@@ -0,0 +1,455 @@
+use crate::color::Color;
+use crate::geometry::bounds::Bounds;
+use crate::geometry::bvh::Hierarchy;
+use crate::geometry::ray::Ray;
+use crate::geometry::triangle::{face_normal, moller_trumbore};
+use crate::geometry::{Hit, HitData, Shape};
+use crate::prelude::*;
+use nalgebra::{Unit, Vector2};
+
+/// An indexed triangle mesh. Vertex attributes are shared between triangles, which
+/// cost 16 bytes each plus their share of the bounding volume hierarchy used to
+/// intersect them, against well over a hundred for a standalone triangle object.
+///
+/// Hits report the index of the triangle in the index buffer as
+/// [`HitData::primitive`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mesh {
+    positions: Vec<Point>,
+    normals: Vec<Vector>,
+    uvs: Vec<Vector2<f32>>,
+    colors: Vec<Color>,
+    indices: Vec<[u32; 3]>,
+    hierarchy: Hierarchy,
+}
+
+impl Mesh {
+    pub fn new(positions: Vec<Point>, indices: Vec<[u32; 3]>) -> Self {
+        assert!(
+            indices
+                .iter()
+                .flatten()
+                .all(|&i| (i as usize) < positions.len()),
+            "vertex index out of range"
+        );
+        let bounds = indices
+            .iter()
+            .map(|t| Bounds::from_points(t.iter().map(|&i| positions[i as usize])))
+            .collect::<Vec<_>>();
+        Self {
+            hierarchy: Hierarchy::new(&bounds, (0..indices.len() as u32).collect()),
+            positions,
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            colors: Vec::new(),
+            indices,
+        }
+    }
+
+    /// Sets per-vertex normals, interpolated across each triangle.
+    pub fn with_normals(mut self, normals: Vec<Vector>) -> Self {
+        assert_eq!(normals.len(), self.positions.len());
+        self.normals = normals;
+        self
+    }
+
+    /// Sets per-vertex texture coordinates.
+    pub fn with_uvs(mut self, uvs: Vec<Vector2<f32>>) -> Self {
+        assert_eq!(uvs.len(), self.positions.len());
+        self.uvs = uvs;
+        self
+    }
+
+    /// Sets per-vertex colors, such as those of a scanned model.
+    pub fn with_colors(mut self, colors: Vec<Color>) -> Self {
+        assert_eq!(colors.len(), self.positions.len());
+        self.colors = colors;
+        self
+    }
+
+    pub fn positions(&self) -> &[Point] {
+        &self.positions
+    }
+
+    pub fn normals(&self) -> &[Vector] {
+        &self.normals
+    }
+
+    pub fn uvs(&self) -> &[Vector2<f32>] {
+        &self.uvs
+    }
+
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    pub fn indices(&self) -> &[[u32; 3]] {
+        &self.indices
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn triangle(&self, primitive: usize) -> [Point; 3] {
+        let [a, b, c] = self.indices[primitive];
+        [
+            self.positions[a as usize],
+            self.positions[b as usize],
+            self.positions[c as usize],
+        ]
+    }
+
+    /// Interpolates a per-vertex attribute at barycentric coordinates `uv` of a
+    /// triangle.
+    pub fn interpolate<T>(&self, attribute: &[T], primitive: usize, uv: Vector2<f32>) -> T
+    where
+        T: Copy + std::ops::Mul<f32, Output = T> + std::ops::Add<Output = T>,
+    {
+        let [a, b, c] = self.indices[primitive];
+        attribute[a as usize] * (1.0 - uv.x - uv.y)
+            + attribute[b as usize] * uv.x
+            + attribute[c as usize] * uv.y
+    }
+
+    /// The texture coordinates at a hit, if the mesh has any.
+    pub fn uv_at(&self, data: &HitData) -> Option<Vector2<f32>> {
+        match (data.primitive, data.uv) {
+            (Some(primitive), Some(uv)) if !self.uvs.is_empty() => {
+                Some(self.interpolate(&self.uvs, primitive, uv))
+            }
+            _ => None,
+        }
+    }
+
+    /// The vertex color at a hit, if the mesh has any.
+    pub fn color_at(&self, data: &HitData) -> Option<Color> {
+        match (data.primitive, data.uv) {
+            (Some(primitive), Some(uv)) if !self.colors.is_empty() => {
+                Some(self.interpolate(&self.colors, primitive, uv))
+            }
+            _ => None,
+        }
+    }
+
+    fn intersect_triangle(&self, primitive: usize, ray: &Ray) -> Option<Hit> {
+        let [p1, p2, p3] = self.triangle(primitive);
+        let (e1, e2) = (p2 - p1, p3 - p1);
+        let twice_area = e1.cross(&e2).norm();
+        moller_trumbore(p1, e1, e2, twice_area, ray.orig, ray.dir).map(|(t, uv)| Hit {
+            t,
+            data: HitData {
+                uv: Some(uv),
+                primitive: Some(primitive),
+            },
+        })
+    }
+
+    fn face_normal(&self, primitive: usize) -> Unit<Vector> {
+        let [p1, p2, p3] = self.triangle(primitive);
+        face_normal(p2 - p1, p3 - p1)
+    }
+}
+
+// the distance from `p` to the closest point of a triangle, found by the region
+// of the triangle's plane `p` projects to, as in Ericson's Real-Time Collision
+// Detection, 5.1.5
+fn distance(p: Point, [a, b, c]: [Point; 3]) -> f32 {
+    let (ab, ac) = (b - a, c - a);
+    let ap = p - a;
+    let (d1, d2) = (ab.dot(&ap), ac.dot(&ap));
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return ap.norm();
+    }
+    let bp = p - b;
+    let (d3, d4) = (ab.dot(&bp), ac.dot(&bp));
+    if d3 >= 0.0 && d4 <= d3 {
+        return bp.norm();
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return (ap - ab * (d1 / (d1 - d3))).norm();
+    }
+    let cp = p - c;
+    let (d5, d6) = (ab.dot(&cp), ac.dot(&cp));
+    if d6 >= 0.0 && d5 <= d6 {
+        return cp.norm();
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return (ap - ac * (d2 / (d2 - d6))).norm();
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 >= d3 && d5 >= d6 {
+        return (bp - (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)))).norm();
+    }
+    // inside the triangle, degenerate ones give NaN and are never the closest
+    let sum = va + vb + vc;
+    (ap - ab * (vb / sum) - ac * (vc / sum)).norm()
+}
+
+impl Shape for Mesh {
+    type Hits = Vec<Hit>;
+
+    fn intersect(&self, ray: Ray) -> Self::Hits {
+        let mut hits = Vec::new();
+        self.hierarchy
+            .traverse(&ray, f32::NEG_INFINITY, f32::INFINITY, |p| {
+                hits.extend(self.intersect_triangle(p as usize, &ray));
+                f32::INFINITY
+            });
+        hits
+    }
+
+    /// Without hit data this searches for the triangle closest to `point`,
+    /// prefer [`Shape::normal_at_hit`]. An empty mesh has no surface, and gives
+    /// an arbitrary normal.
+    fn normal_at(&self, point: Point) -> Unit<Vector> {
+        self.hierarchy
+            .nearest(&point, f32::INFINITY, |p| {
+                distance(point, self.triangle(p as usize))
+            })
+            .map_or_else(Vector::z_axis, |p| self.face_normal(p as usize))
+    }
+
+    fn normal_at_hit(&self, point: Point, data: &HitData) -> Unit<Vector> {
+        match (data.primitive, data.uv) {
+            (Some(primitive), Some(uv)) if !self.normals.is_empty() => {
+                Unit::new_normalize(self.interpolate(&self.normals, primitive, uv))
+            }
+            (Some(primitive), _) => self.face_normal(primitive),
+            _ => self.normal_at(point),
+        }
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.hierarchy.bounds()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::mesh::{distance, Mesh};
+    use crate::geometry::ray::Ray;
+    use crate::geometry::triangle::Triangle;
+    use crate::geometry::{Hittable, Shape};
+    use crate::prelude::*;
+    use approx::assert_abs_diff_eq;
+    use itertools::Itertools;
+    use nalgebra::{point, vector, Vector2};
+
+    fn quad() -> Mesh {
+        Mesh::new(
+            vec![
+                point![-1.0, -1.0, 0.0],
+                point![1.0, -1.0, 0.0],
+                point![1.0, 1.0, 0.0],
+                point![-1.0, 1.0, 0.0],
+            ],
+            vec![[0, 1, 2], [0, 2, 3]],
+        )
+    }
+
+    // a bumpy height field of `n * n * 2` triangles over [0, 1]²
+    fn terrain(n: u32) -> Mesh {
+        let positions = (0..=n)
+            .cartesian_product(0..=n)
+            .map(|(i, j)| {
+                let (x, z) = (i as f32 / n as f32, j as f32 / n as f32);
+                point![x, 0.1 * (7.0 * x).sin() * (5.0 * z).cos(), z]
+            })
+            .collect();
+        let vertex = |i: u32, j: u32| i * (n + 1) + j;
+        let indices = (0..n)
+            .cartesian_product(0..n)
+            .flat_map(|(i, j)| {
+                vec![
+                    [vertex(i, j), vertex(i, j + 1), vertex(i + 1, j)],
+                    [vertex(i + 1, j), vertex(i, j + 1), vertex(i + 1, j + 1)],
+                ]
+            })
+            .collect();
+        Mesh::new(positions, indices)
+    }
+
+    #[test]
+    fn test_hit_reports_primitive() {
+        let mesh = quad();
+        let r = Ray::new([0.5, -0.5, 2.0], [0.0, 0.0, -1.0]);
+        let hits = mesh.intersect(r);
+        assert_eq!(hits.len(), 1);
+        assert_abs_diff_eq!(hits[0].t, 2.0);
+        assert_eq!(hits[0].data.primitive, Some(0));
+
+        let r = Ray::new([-0.5, 0.5, 2.0], [0.0, 0.0, -1.0]);
+        assert_eq!(mesh.intersect(r)[0].data.primitive, Some(1));
+
+        let r = Ray::new([1.5, 0.5, 2.0], [0.0, 0.0, -1.0]);
+        assert!(mesh.intersect(r).is_empty());
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mesh = terrain(24);
+        assert_eq!(mesh.len(), 24 * 24 * 2);
+        let rays = (0..15).cartesian_product(0..15).map(|(i, j)| {
+            let target = point![i as f32 / 14.0, 0.0, j as f32 / 14.0];
+            Ray::from_points([0.3 * i as f32 - 2.0, 3.0, 1.5 - 0.2 * j as f32], target)
+        });
+        for r in rays {
+            let mut fast = mesh
+                .intersect(r)
+                .iter()
+                .map(|h| (h.data.primitive.unwrap(), h.t))
+                .collect::<Vec<_>>();
+            fast.sort_by_key(|&(p, _)| p);
+            let slow = (0..mesh.len())
+                .filter_map(|p| {
+                    let [a, b, c] = mesh.triangle(p);
+                    let hits = Triangle::new(a, b, c).intersect(r);
+                    hits.first().map(|h| (p, h.t))
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(fast.len(), slow.len());
+            for ((p, t), (q, s)) in fast.into_iter().zip(slow) {
+                assert_eq!(p, q);
+                assert_abs_diff_eq!(t, s, epsilon = 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_interpolated_normals_and_uvs() {
+        let mesh = quad()
+            .with_normals(vec![
+                vector![-1.0, 0.0, 1.0],
+                vector![1.0, 0.0, 1.0],
+                vector![1.0, 0.0, 1.0],
+                vector![-1.0, 0.0, 1.0],
+            ])
+            .with_uvs(vec![
+                Vector2::new(0.0, 0.0),
+                Vector2::new(1.0, 0.0),
+                Vector2::new(1.0, 1.0),
+                Vector2::new(0.0, 1.0),
+            ]);
+        let r = Ray::new([0.0, -0.5, 2.0], [0.0, 0.0, -1.0]);
+        let hit = mesh.intersect(r)[0];
+        assert_abs_diff_eq!(
+            mesh.normal_at_hit(r.trace(hit.t), &hit.data).into_inner(),
+            Vector::z(),
+            epsilon = 1e-6
+        );
+        assert_abs_diff_eq!(
+            mesh.uv_at(&hit.data).unwrap(),
+            Vector2::new(0.5, 0.25),
+            epsilon = 1e-6
+        );
+        assert_eq!(quad().uv_at(&hit.data), None);
+    }
+
+    #[test]
+    fn test_flat_normals() {
+        let mesh = quad();
+        assert_eq!(mesh.normal_at(point![0.2, 0.3, 0.0]), Vector::z_axis());
+        let r = Ray::new([0.0, -0.5, 2.0], [0.0, 0.0, -1.0]);
+        let hit = mesh.intersect(r)[0];
+        assert_eq!(
+            mesh.normal_at_hit(r.trace(hit.t), &hit.data),
+            Vector::z_axis()
+        );
+    }
+
+    #[test]
+    fn test_transformed_mesh_object() {
+        let obj = terrain(8).into_object().scaled(10.0, 10.0, 10.0);
+        let r = Ray::new([5.0, 10.0, 5.0], [0.0, -1.0, 0.0]);
+        let inter = obj.intersect(r);
+        let (t, ctx) = inter.hit().unwrap();
+        assert!(ctx.data.primitive.is_some());
+        assert_abs_diff_eq!(
+            r.trace(t).y,
+            (0.7f32 * 5.0).sin() * (0.5f32 * 5.0).cos(),
+            epsilon = 0.2
+        );
+        assert!(ctx.normal_at(r.trace(t)).y > 0.5);
+    }
+
+    #[test]
+    fn test_empty() {
+        let mesh = Mesh::new(Vec::new(), Vec::new());
+        assert!(mesh.is_empty());
+        assert!(mesh
+            .intersect(Ray::new([0.0, 0.0, 0.0], [1.0, 0.0, 0.0]))
+            .is_empty());
+        assert_eq!(mesh.normal_at(Point::origin()).norm(), 1.0);
+    }
+
+    #[test]
+    fn test_normal_of_closest_triangle() {
+        // the wall's plane passes through the point, but the wall itself is far
+        // from it
+        let mesh = Mesh::new(
+            vec![
+                point![0.0, 0.0, 0.0],
+                point![10.0, 0.0, 0.0],
+                point![0.0, 0.0, 10.0],
+                point![5.0, -1.0, -20.0],
+                point![5.0, 1.0, -20.0],
+                point![5.0, 0.0, -30.0],
+            ],
+            vec![[0, 1, 2], [3, 4, 5]],
+        );
+        assert_eq!(mesh.normal_at(point![5.0, 0.1, 2.0]), -Vector::y_axis());
+        assert_eq!(mesh.normal_at(point![5.0, 0.0, -21.0]).x.abs(), 1.0);
+        // beyond the floor's edge, but still closer to it than to the wall
+        assert_eq!(mesh.normal_at(point![12.0, 0.0, 12.0]), -Vector::y_axis());
+        assert_eq!(mesh.normal_at(point![f32::NAN, 0.0, 0.0]).norm(), 1.0);
+    }
+
+    #[test]
+    fn test_closest_triangle_matches_brute_force() {
+        let mesh = terrain(16);
+        let points = (0..9).cartesian_product(0..9).map(|(i, j)| {
+            point![
+                0.15 * i as f32 - 0.1,
+                0.05 * (i + j) as f32 - 0.4,
+                0.15 * j as f32 - 0.1
+            ]
+        });
+        for p in points {
+            let closest = (0..mesh.len())
+                .map(|q| distance(p, mesh.triangle(q)))
+                .fold(f32::INFINITY, f32::min);
+            let found = mesh
+                .hierarchy
+                .nearest(&p, f32::INFINITY, |q| {
+                    distance(p, mesh.triangle(q as usize))
+                })
+                .unwrap();
+            assert_eq!(distance(p, mesh.triangle(found as usize)), closest);
+        }
+    }
+
+    #[test]
+    fn test_axis_parallel_ray_on_node_face() {
+        // the ray runs along the z = 0 face of the mesh's box and crosses the
+        // triangle's bottom edge
+        let mesh = Mesh::new(
+            vec![
+                point![0.5, 0.0, 0.0],
+                point![0.5, 1.0, 0.0],
+                point![0.5, 0.0, 1.0],
+            ],
+            vec![[0, 1, 2]],
+        );
+        let r = Ray::new([-1.0, 0.2, 0.0], [1.0, 0.0, 0.0]);
+        let hits = mesh.intersect(r);
+        assert_eq!(hits.len(), 1);
+        assert_abs_diff_eq!(hits[0].t, 1.5);
+    }
+}
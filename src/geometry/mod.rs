@@ -1,67 +1,155 @@
+use crate::geometry::bounds::Bounds;
 use crate::geometry::ray::Ray;
+use crate::prelude::*;
 use crate::util::float_ord::FloatRange;
+use crate::util::transform::{GenericTransform, TryInverse};
 use btreemultimap::BTreeMultiMap;
 use decorum::Total;
+use nalgebra::{Matrix4, Unit, Vector2};
 use std::cmp::Ordering;
 use std::fmt::{Debug, Formatter};
 use std::hash::Hash;
-use std::iter;
 use std::iter::FromIterator;
 use std::ops::RangeBounds;
 use std::sync::atomic::{AtomicU64, Ordering::SeqCst};
 
+pub mod bounds;
+pub mod bvh;
+pub mod cone;
+pub mod csg;
+pub mod cube;
+pub mod cylinder;
+pub mod group;
+pub mod mesh;
+pub mod plane;
 pub mod ray;
 pub mod sphere;
+pub mod triangle;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Id(u64);
 
-impl Id {
-    const COUNTER: AtomicU64 = AtomicU64::new(0);
+static COUNTER: AtomicU64 = AtomicU64::new(0);
 
+impl Id {
     pub fn new() -> Self {
-        Self(Self::COUNTER.fetch_add(1, SeqCst))
+        Self(COUNTER.fetch_add(1, SeqCst))
     }
 
     pub fn count() -> u64 {
-        Self::COUNTER.load(SeqCst)
+        COUNTER.load(SeqCst)
     }
 }
 
 #[derive(Clone, Debug)]
-pub struct Object<S> {
+pub struct Object<S, T = Affine> {
     id: Id,
     shape: S,
+    transform: T,
+    // kept alongside `transform`, since every ray and normal needs it
+    inverse: T,
 }
 
 impl<S> Object<S> {
     pub fn new(shape: S) -> Self {
         let id = Id::new();
-        Self { id, shape }
+        Self {
+            id,
+            shape,
+            transform: Affine::identity(),
+            inverse: Affine::identity(),
+        }
+    }
+
+    pub fn translated(self, x: f32, y: f32, z: f32) -> Self {
+        self.then(Matrix4::new_translation(&Vector::new(x, y, z)))
+    }
+
+    pub fn scaled(self, x: f32, y: f32, z: f32) -> Self {
+        self.then(Matrix4::new_nonuniform_scaling(&Vector::new(x, y, z)))
+    }
+
+    pub fn rotated_x(self, angle: f32) -> Self {
+        self.then(Matrix4::from_axis_angle(&Vector::x_axis(), angle))
+    }
+
+    pub fn rotated_y(self, angle: f32) -> Self {
+        self.then(Matrix4::from_axis_angle(&Vector::y_axis(), angle))
+    }
+
+    pub fn rotated_z(self, angle: f32) -> Self {
+        self.then(Matrix4::from_axis_angle(&Vector::z_axis(), angle))
+    }
+
+    /// Moves each coordinate in proportion to the other two, e.g. `xy` adds `y`
+    /// times itself to `x`.
+    pub fn sheared(self, xy: f32, xz: f32, yx: f32, yz: f32, zx: f32, zy: f32) -> Self {
+        #[rustfmt::skip]
+        let m = Matrix4::new(
+            1.0, xy,  xz,  0.0,
+            yx,  1.0, yz,  0.0,
+            zx,  zy,  1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        );
+        self.then(m)
+    }
+
+    // chained builders apply in call order, each one after the previous ones
+    fn then(self, m: Matrix4<f32>) -> Self {
+        let transform = Affine::from_matrix_unchecked(m) * self.transform;
+        self.with_transform(transform)
+    }
+}
+
+impl<S, T> Object<S, T> {
+    /// Replaces the object-to-world transform, which can be any of the `prelude`
+    /// transform types.
+    ///
+    /// # Panics
+    ///
+    /// If the transform is singular, such as a scaling by zero.
+    pub fn with_transform<U: TryInverse>(self, transform: U) -> Object<S, U> {
+        let inverse = transform
+            .try_inverse()
+            .expect("object transforms must be invertible");
+        Object {
+            id: self.id,
+            shape: self.shape,
+            transform,
+            inverse,
+        }
+    }
+
+    pub fn shape(&self) -> &S {
+        &self.shape
+    }
+
+    pub fn transform(&self) -> &T {
+        &self.transform
     }
 }
 
-impl<S> PartialEq for Object<S> {
+impl<S, T> PartialEq for Object<S, T> {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
     }
 }
 
-impl<S> Eq for Object<S> {}
+impl<S, T> Eq for Object<S, T> {}
 
-impl<S> PartialOrd for Object<S> {
+impl<S, T> PartialOrd for Object<S, T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         PartialOrd::partial_cmp(&self.id, &other.id)
     }
 }
 
-impl<S> Ord for Object<S> {
+impl<S, T> Ord for Object<S, T> {
     fn cmp(&self, other: &Self) -> Ordering {
         Ord::cmp(&self.id, &other.id)
     }
 }
 
-impl<S> Hash for Object<S> {
+impl<S, T> Hash for Object<S, T> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.id.hash(state);
     }
@@ -71,27 +159,177 @@ pub trait Hittable {
     fn id(&self) -> Id;
 
     fn intersect(&self, ray: Ray) -> Intersection;
+
+    /// Adds the hits of `ray` to `hits`, for a hittable inside groups whose
+    /// inverse transforms are `parents`, outermost first. Containers pass it on
+    /// to their children, so that hits are not gathered again at every level.
+    fn intersect_into<'a>(
+        &'a self,
+        ray: Ray,
+        parents: &mut Vec<&'a dyn GenericTransform<f32, 3>>,
+        hits: &mut Vec<(f32, HitContext<'a>)>,
+    ) {
+        hits.extend(self.intersect(ray).all().map(|(t, ctx)| {
+            let mut ctx = ctx.clone();
+            ctx.parents.extend(parents.iter().rev());
+            (t, ctx)
+        }));
+    }
+
+    /// The outward unit normal at a world-space point on the surface. Hittables
+    /// without a surface, such as empty groups, give an arbitrary unit vector.
+    fn normal_at(&self, point: Point) -> Unit<Vector>;
+
+    /// Like [`Hittable::normal_at`], using what the intersection recorded about
+    /// the hit, such as barycentric coordinates for interpolated normals.
+    fn normal_at_hit(&self, point: Point, _data: &HitData) -> Unit<Vector> {
+        self.normal_at(point)
+    }
+
+    /// Whether `other` is this hittable or one of its parts.
+    fn includes(&self, other: &dyn Hittable) -> bool {
+        self.id() == other.id()
+    }
+
+    /// The world-space box around the surface.
+    fn bounds(&self) -> Bounds;
+}
+
+impl<H: Hittable + ?Sized> Hittable for Box<H> {
+    fn id(&self) -> Id {
+        (**self).id()
+    }
+
+    fn intersect(&self, ray: Ray) -> Intersection {
+        (**self).intersect(ray)
+    }
+
+    fn intersect_into<'a>(
+        &'a self,
+        ray: Ray,
+        parents: &mut Vec<&'a dyn GenericTransform<f32, 3>>,
+        hits: &mut Vec<(f32, HitContext<'a>)>,
+    ) {
+        (**self).intersect_into(ray, parents, hits)
+    }
+
+    fn normal_at(&self, point: Point) -> Unit<Vector> {
+        (**self).normal_at(point)
+    }
+
+    fn normal_at_hit(&self, point: Point, data: &HitData) -> Unit<Vector> {
+        (**self).normal_at_hit(point, data)
+    }
+
+    fn includes(&self, other: &dyn Hittable) -> bool {
+        (**self).includes(other)
+    }
+
+    fn bounds(&self) -> Bounds {
+        (**self).bounds()
+    }
+}
+
+// takes the inverse of the object-to-world transform
+fn normal_to_world<T>(inverse: &T, local: Unit<Vector>) -> Unit<Vector>
+where
+    T: GenericTransform<f32, 3> + ?Sized,
+{
+    // normals transform by the inverse transpose: the k-th component of
+    // `M^-T n` is the dot product of `n` with the k-th column of `M^-1`
+    let column = |axis: Vector| inverse.transform_vector(&axis);
+    Unit::new_normalize(Vector::new(
+        column(Vector::x()).dot(&local),
+        column(Vector::y()).dot(&local),
+        column(Vector::z()).dot(&local),
+    ))
 }
 
-impl<S: Shape> Hittable for Object<S> {
+// whether the surface of `h` passes through `point`, found by probing a short
+// segment across it along the normal, with a length relative to the size of `h`
+fn is_on_surface(h: &dyn Hittable, point: Point) -> bool {
+    let size = h
+        .bounds()
+        .size()
+        .iter()
+        .copied()
+        .filter(|s| s.is_finite())
+        .fold(0.0, f32::max);
+    let probe_len = 1e-3 * if size > 0.0 { size } else { 1.0 };
+    let n = h.normal_at(point).into_inner();
+    let probe = Ray {
+        orig: point - n * probe_len,
+        dir: n,
+    };
+    h.intersect(probe)
+        .all()
+        .any(|(t, _)| (0.0..=2.0 * probe_len).contains(&t))
+}
+
+impl<S, T> Hittable for Object<S, T>
+where
+    S: Shape,
+    T: GenericTransform<f32, 3>,
+    <S::Hits as IntoIterator>::Item: Into<Hit>,
+{
     fn id(&self) -> Id {
         self.id
     }
 
     fn intersect(&self, ray: Ray) -> Intersection {
         self.shape
-            .intersect(ray)
+            .intersect(ray.transform(&self.inverse))
             .into_iter()
-            .zip(iter::repeat(HitContext { obj_hit: self }))
+            .map(|hit| {
+                let Hit { t, data } = hit.into();
+                (
+                    t,
+                    HitContext {
+                        obj_hit: self,
+                        data,
+                        parents: Vec::new(),
+                        flipped: false,
+                    },
+                )
+            })
             .collect()
     }
+
+    fn normal_at(&self, point: Point) -> Unit<Vector> {
+        let local = self.inverse.transform_point(&point);
+        normal_to_world(&self.inverse, self.shape.normal_at(local))
+    }
+
+    fn normal_at_hit(&self, point: Point, data: &HitData) -> Unit<Vector> {
+        let local = self.inverse.transform_point(&point);
+        normal_to_world(&self.inverse, self.shape.normal_at_hit(local, data))
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.shape.bounds().transform(&self.transform)
+    }
 }
 
-pub trait Shape {
+/// A surface in object space. `Hits` yields distances along the ray, either as bare
+/// `f32`s or as [`Hit`]s carrying extra data about each hit.
+pub trait Shape
+where
+    <Self::Hits as IntoIterator>::Item: Into<Hit>,
+{
     // not object safe
-    type Hits: IntoIterator<Item = f32>;
+    type Hits: IntoIterator;
     fn intersect(&self, ray: Ray) -> Self::Hits;
 
+    /// The outward unit normal at an object-space point on the surface.
+    fn normal_at(&self, point: Point) -> Unit<Vector>;
+
+    fn normal_at_hit(&self, point: Point, _data: &HitData) -> Unit<Vector> {
+        self.normal_at(point)
+    }
+
+    /// The object-space box around the surface.
+    fn bounds(&self) -> Bounds;
+
     fn into_object(self) -> Object<Self>
     where
         Self: Sized,
@@ -176,7 +414,237 @@ impl<'a> FromIterator<(f32, HitContext<'a>)> for Intersection<'a> {
     }
 }
 
-#[derive(Debug, Clone, PartialOrd, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialOrd, PartialEq)]
+pub struct Hit {
+    pub t: f32,
+    pub data: HitData,
+}
+
+impl From<f32> for Hit {
+    fn from(t: f32) -> Self {
+        Hit {
+            t,
+            data: HitData::default(),
+        }
+    }
+}
+
+/// Per-hit information a shape records during intersection.
+#[derive(Debug, Copy, Clone, Default, PartialOrd, PartialEq)]
+pub struct HitData {
+    /// Barycentric coordinates of the hit on a triangle, as the weights of its
+    /// second and third vertices.
+    pub uv: Option<Vector2<f32>>,
+    /// The index of the hit triangle within a mesh.
+    pub primitive: Option<usize>,
+}
+
+#[derive(Clone)]
 pub struct HitContext<'a> {
     pub obj_hit: &'a dyn Hittable,
+    pub data: HitData,
+    /// The inverse transforms of the groups enclosing `obj_hit`, innermost
+    /// first.
+    pub parents: Vec<&'a dyn GenericTransform<f32, 3>>,
+    /// Whether `obj_hit` was carved out of an enclosing solid, so that its
+    /// normals point the other way.
+    pub flipped: bool,
+}
+
+impl HitContext<'_> {
+    /// The world-space normal at `point`, which should lie on the hit surface.
+    pub fn normal_at(&self, point: Point) -> Unit<Vector> {
+        let local = self
+            .parents
+            .iter()
+            .rev()
+            .fold(point, |p, t| t.transform_point(&p));
+        let mut normal = self.obj_hit.normal_at_hit(local, &self.data);
+        if self.flipped {
+            normal = -normal;
+        }
+        self.parents
+            .iter()
+            .fold(normal, |n, &t| normal_to_world(t, n))
+    }
+}
+
+// a hittable appears in a single place of the scene, so the parents and
+// `flipped` follow from `obj_hit`
+impl PartialEq for HitContext<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.obj_hit == other.obj_hit && self.data == other.data
+    }
+}
+
+impl PartialOrd for HitContext<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match self.obj_hit.partial_cmp(other.obj_hit) {
+            Some(Ordering::Equal) => self.data.partial_cmp(&other.data),
+            ord => ord,
+        }
+    }
+}
+
+impl Debug for HitContext<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HitContext")
+            .field("obj_hit", &self.obj_hit)
+            .field("data", &self.data)
+            .field("depth", &self.parents.len())
+            .field("flipped", &self.flipped)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::ray::Ray;
+    use crate::geometry::sphere::Sphere;
+    use crate::geometry::{Hit, Hittable, Shape};
+    use crate::prelude::*;
+    use approx::assert_abs_diff_eq;
+    use nalgebra::{point, vector, Rotation3};
+    use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4};
+
+    fn hits(obj: &dyn Hittable, ray: Ray) -> Vec<f32> {
+        obj.intersect(ray).all().map(|(t, _)| t).collect()
+    }
+
+    /// The distances at which a shape is hit by the ray from `orig` along `dir`,
+    /// in increasing order.
+    pub(crate) fn sorted_hits<S: Shape>(shape: &S, orig: [f32; 3], dir: [f32; 3]) -> Vec<f32>
+    where
+        <S::Hits as IntoIterator>::Item: Into<Hit>,
+    {
+        let mut ts = shape
+            .intersect(Ray::new(orig, dir))
+            .into_iter()
+            .map(|hit| hit.into().t)
+            .collect::<Vec<_>>();
+        ts.sort_by(f32::total_cmp);
+        ts
+    }
+
+    #[test]
+    fn test_distinct_ids() {
+        let a = Sphere::default().into_object();
+        let b = Sphere::default().into_object();
+        assert_ne!(a.id(), b.id());
+    }
+
+    #[test]
+    fn test_builders() {
+        let p = point![1.0, 0.0, 1.0];
+        let obj = Sphere::default().into_object().rotated_x(FRAC_PI_2);
+        assert_abs_diff_eq!(
+            obj.transform().transform_point(&p),
+            point![1.0, -1.0, 0.0],
+            epsilon = 1e-6
+        );
+        let obj = Sphere::default().into_object().scaled(5.0, 5.0, 5.0);
+        assert_abs_diff_eq!(obj.transform().transform_point(&p), point![5.0, 0.0, 5.0]);
+        let obj = Sphere::default().into_object().translated(10.0, 5.0, 7.0);
+        assert_abs_diff_eq!(obj.transform().transform_point(&p), point![11.0, 5.0, 8.0]);
+        let obj = Sphere::default().into_object().rotated_y(FRAC_PI_2);
+        assert_abs_diff_eq!(
+            obj.transform().transform_point(&p),
+            point![1.0, 0.0, -1.0],
+            epsilon = 1e-6
+        );
+        let obj = Sphere::default().into_object().rotated_z(FRAC_PI_4);
+        assert_abs_diff_eq!(
+            obj.transform().transform_point(&point![0.0, 1.0, 0.0]),
+            point![-FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0],
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "object transforms must be invertible")]
+    fn test_singular_transform() {
+        let _ = Sphere::default().into_object().scaled(0.0, 1.0, 1.0);
+    }
+
+    #[test]
+    fn test_sheared() {
+        let p = point![2.0, 3.0, 4.0];
+        let cases = [
+            ([1.0, 0.0, 0.0, 0.0, 0.0, 0.0], point![5.0, 3.0, 4.0]),
+            ([0.0, 1.0, 0.0, 0.0, 0.0, 0.0], point![6.0, 3.0, 4.0]),
+            ([0.0, 0.0, 1.0, 0.0, 0.0, 0.0], point![2.0, 5.0, 4.0]),
+            ([0.0, 0.0, 0.0, 1.0, 0.0, 0.0], point![2.0, 7.0, 4.0]),
+            ([0.0, 0.0, 0.0, 0.0, 1.0, 0.0], point![2.0, 3.0, 6.0]),
+            ([0.0, 0.0, 0.0, 0.0, 0.0, 1.0], point![2.0, 3.0, 7.0]),
+        ];
+        for &([xy, xz, yx, yz, zx, zy], expected) in cases.iter() {
+            let obj = Sphere::default()
+                .into_object()
+                .sheared(xy, xz, yx, yz, zx, zy);
+            assert_eq!(obj.transform().transform_point(&p), expected);
+        }
+    }
+
+    #[test]
+    fn test_chained_builders_apply_in_order() {
+        let obj = Sphere::default()
+            .into_object()
+            .rotated_x(FRAC_PI_2)
+            .scaled(5.0, 5.0, 5.0)
+            .translated(10.0, 5.0, 7.0);
+        assert_abs_diff_eq!(
+            obj.transform().transform_point(&point![1.0, 0.0, 1.0]),
+            point![15.0, 0.0, 7.0],
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn test_transform_aliases() {
+        let ray = Ray::new([0.0, 0.0, -5.0], [0.0, 0.0, 1.0]);
+        let expected = [5.0, 9.0];
+
+        let affine = Sphere::default()
+            .into_object()
+            .scaled(1.0, 1.0, 2.0)
+            .translated(0.0, 0.0, 2.0);
+        assert_abs_diff_eq!(hits(&affine, ray).as_slice(), expected.as_ref());
+
+        let projective = affine
+            .clone()
+            .with_transform(Projective::from_matrix_unchecked(
+                affine.transform().to_homogeneous(),
+            ));
+        assert_abs_diff_eq!(hits(&projective, ray).as_slice(), expected.as_ref());
+
+        let isometry = Sphere::default()
+            .into_object()
+            .with_transform(Isometry::translation(0.0, 0.0, 1.0));
+        assert_abs_diff_eq!(hits(&isometry, ray).as_slice(), [5.0, 7.0].as_ref());
+
+        let similarity = Sphere::default()
+            .into_object()
+            .with_transform(Similarity::new(
+                vector![0.0, 0.0, 1.0],
+                Vector::zeros(),
+                2.0,
+            ));
+        assert_abs_diff_eq!(hits(&similarity, ray).as_slice(), [4.0, 8.0].as_ref());
+    }
+
+    #[test]
+    fn test_rotated_normal_with_isometry() {
+        let rotation = Rotation3::from_axis_angle(&Vector::y_axis(), FRAC_PI_2);
+        let obj = Sphere::default()
+            .into_object()
+            .with_transform(Isometry::from_parts(
+                Translation::new(0.0, 3.0, 0.0),
+                rotation.into(),
+            ));
+        assert_abs_diff_eq!(
+            obj.normal_at(point![0.0, 3.0, 1.0]).into_inner(),
+            Vector::z(),
+            epsilon = 1e-6
+        );
+    }
 }
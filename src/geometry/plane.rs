@@ -0,0 +1,118 @@
+use crate::geometry::bounds::Bounds;
+use crate::geometry::ray::Ray;
+use crate::geometry::Shape;
+use crate::prelude::*;
+use arrayvec::ArrayVec;
+use nalgebra::Unit;
+
+/// The points `p` with `n · p = d`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Plane {
+    pub n: Unit<Vector>,
+    pub d: f32,
+}
+
+impl Plane {
+    pub fn new(normal: [f32; 3], offset: f32) -> Self {
+        Self {
+            n: Unit::new_normalize(normal.into()),
+            d: offset,
+        }
+    }
+}
+
+/// The xz-plane, facing up.
+impl Default for Plane {
+    fn default() -> Self {
+        Plane {
+            n: Vector::y_axis(),
+            d: 0.0,
+        }
+    }
+}
+
+impl Shape for Plane {
+    type Hits = ArrayVec<f32, 1>;
+
+    fn intersect(&self, Ray { orig, dir }: Ray) -> Self::Hits {
+        let mut solution = ArrayVec::new();
+        let n_dot_dir = self.n.dot(&dir);
+        // rays parallel to the plane miss it, and so do coplanar ones since an
+        // infinitely thin plane is invisible edge-on
+        if n_dot_dir.abs() > 1e-6 * dir.norm() {
+            solution.push((self.d - self.n.dot(&orig.coords)) / n_dot_dir);
+        }
+        solution
+    }
+
+    fn normal_at(&self, _point: Point) -> Unit<Vector> {
+        self.n
+    }
+
+    fn bounds(&self) -> Bounds {
+        Bounds::infinite()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::plane::Plane;
+    use crate::geometry::ray::Ray;
+    use crate::geometry::{Hittable, Shape};
+    use crate::prelude::*;
+    use approx::assert_abs_diff_eq;
+    use nalgebra::point;
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn test_normal_is_constant() {
+        let p = Plane::default();
+        assert_eq!(p.normal_at(point![0.0, 0.0, 0.0]), Vector::y_axis());
+        assert_eq!(p.normal_at(point![10.0, 0.0, -10.0]), Vector::y_axis());
+        assert_eq!(p.normal_at(point![-5.0, 0.0, 150.0]), Vector::y_axis());
+    }
+
+    #[test]
+    fn test_intersect_parallel() {
+        let r = Ray::new([0.0, 10.0, 0.0], [0.0, 0.0, 1.0]);
+        assert!(Plane::default().intersect(r).is_empty());
+    }
+
+    #[test]
+    fn test_intersect_coplanar() {
+        let r = Ray::new([0.0, 0.0, 0.0], [0.0, 0.0, 1.0]);
+        assert!(Plane::default().intersect(r).is_empty());
+    }
+
+    #[test]
+    fn test_intersect_from_above_and_below() {
+        let p = Plane::default();
+        let r = Ray::new([0.0, 1.0, 0.0], [0.0, -1.0, 0.0]);
+        assert_eq!(p.intersect(r).as_slice(), [1.0]);
+        let r = Ray::new([0.0, -1.0, 0.0], [0.0, 1.0, 0.0]);
+        assert_eq!(p.intersect(r).as_slice(), [1.0]);
+    }
+
+    #[test]
+    fn test_intersect_offset_plane() {
+        let p = Plane::new([0.0, 0.0, 2.0], 3.0);
+        let r = Ray::new([1.0, 1.0, 0.0], [0.0, 1.0, 1.0]);
+        assert_abs_diff_eq!(p.intersect(r)[0], 3.0 * 2f32.sqrt(), epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_transformed_plane_as_wall() {
+        let wall = Plane::default()
+            .into_object()
+            .rotated_x(-FRAC_PI_2)
+            .translated(0.0, 0.0, 5.0);
+        let r = Ray::new([0.0, 1.0, 0.0], [0.0, 0.0, 1.0]);
+        let (t, _) = wall.intersect(r).hit().unwrap();
+        assert_abs_diff_eq!(t, 5.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(
+            wall.normal_at(r.trace(t)).into_inner(),
+            -Vector::z(),
+            epsilon = 1e-6
+        );
+    }
+}
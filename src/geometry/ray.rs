@@ -1,17 +1,20 @@
 use crate::prelude::*;
-use nalgebra::Unit;
+use crate::util::transform::{GenericTransform, InverseTransform};
 
+/// A half-line `orig + t * dir`. Constructors normalize the direction, but
+/// transformed rays keep whatever length the transform gives it, so `t` measures
+/// the same point before and after transforming.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Ray {
     pub orig: Point,
-    pub dir: Unit<Vector>,
+    pub dir: Vector,
 }
 
 impl Ray {
     pub fn new(origin: impl Into<Point>, direction: impl Into<Vector>) -> Self {
         Self {
             orig: origin.into(),
-            dir: Unit::new_normalize(direction.into()),
+            dir: direction.into().normalize(),
         }
     }
 
@@ -19,11 +22,78 @@ impl Ray {
         let orig = origin.into();
         Self {
             orig,
-            dir: Unit::new_normalize(to.into() - orig),
+            dir: (to.into() - orig).normalize(),
         }
     }
 
     pub fn trace(self, t: f32) -> Point {
-        self.orig + self.dir.into_inner() * t
+        self.orig + self.dir * t
+    }
+
+    pub fn transform(&self, transform: &impl GenericTransform<f32, 3>) -> Ray {
+        Ray {
+            orig: transform.transform_point(&self.orig),
+            dir: transform.transform_vector(&self.dir),
+        }
+    }
+
+    pub fn inverse_transform(&self, transform: &impl InverseTransform<f32, 3>) -> Ray {
+        Ray {
+            orig: transform.inverse_transform_point(&self.orig),
+            dir: transform.inverse_transform_vector(&self.dir),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::ray::Ray;
+    use crate::prelude::*;
+    use approx::assert_abs_diff_eq;
+    use nalgebra::{point, vector, Matrix4};
+
+    #[test]
+    fn test_trace() {
+        let r = Ray::new([2.0, 3.0, 4.0], [1.0, 0.0, 0.0]);
+        assert_eq!(r.trace(0.0), point![2.0, 3.0, 4.0]);
+        assert_eq!(r.trace(1.0), point![3.0, 3.0, 4.0]);
+        assert_eq!(r.trace(-1.0), point![1.0, 3.0, 4.0]);
+        assert_eq!(r.trace(2.5), point![4.5, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_translate() {
+        let r = Ray::new([1.0, 2.0, 3.0], [0.0, 1.0, 0.0]);
+        let r2 = r.transform(&Translation::new(3.0, 4.0, 5.0));
+        assert_eq!(r2.orig, point![4.0, 6.0, 8.0]);
+        assert_eq!(r2.dir, vector![0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_scale() {
+        let r = Ray::new([1.0, 2.0, 3.0], [0.0, 1.0, 0.0]);
+        let scaling =
+            Affine::from_matrix_unchecked(Matrix4::new_nonuniform_scaling(&vector![2.0, 3.0, 4.0]));
+        let r2 = r.transform(&scaling);
+        assert_eq!(r2.orig, point![2.0, 6.0, 12.0]);
+        // the direction is not renormalized
+        assert_eq!(r2.dir, vector![0.0, 3.0, 0.0]);
+    }
+
+    #[test]
+    fn test_inverse_transform_keeps_t() {
+        let r = Ray::new([1.0, 2.0, 3.0], [1.0, 1.0, 0.0]);
+        let transform = Similarity::new(vector![1.0, -2.0, 0.5], vector![0.3, 0.0, 1.2], 3.0);
+        let local = r.inverse_transform(&transform);
+        for &t in [-1.0, 0.0, 2.5].iter() {
+            assert_abs_diff_eq!(
+                transform.transform_point(&local.trace(t)),
+                r.trace(t),
+                epsilon = 1e-5
+            );
+        }
+        let back = local.transform(&transform);
+        assert_abs_diff_eq!(back.orig, r.orig, epsilon = 1e-5);
+        assert_abs_diff_eq!(back.dir, r.dir, epsilon = 1e-5);
     }
 }
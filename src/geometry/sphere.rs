@@ -1,7 +1,9 @@
+use crate::geometry::bounds::Bounds;
 use crate::geometry::ray::Ray;
 use crate::geometry::Shape;
 use crate::prelude::*;
 use arrayvec::ArrayVec;
+use nalgebra::Unit;
 
 #[derive(Debug, Copy, Clone, PartialOrd, PartialEq)]
 pub struct Sphere {
@@ -32,21 +34,35 @@ impl Shape for Sphere {
 
     //noinspection RsBorrowChecker
     fn intersect(&self, Ray { orig, dir }: Ray) -> Self::Hits {
+        // `dir` may not be a unit vector when the ray was transformed
         let c_to_orig = orig - self.c;
-        let k_dot_c_to_org = dir.dot(&c_to_orig);
-        let const_term = c_to_orig.magnitude_squared() - self.r.powi(2);
-        let delta = k_dot_c_to_org.powi(2) - const_term;
+        let a = dir.magnitude_squared();
+        let half_b = dir.dot(&c_to_orig);
+        let c = c_to_orig.magnitude_squared() - self.r.powi(2);
+        let delta = half_b.powi(2) - a * c;
 
         let mut solution = ArrayVec::new();
         if delta >= 0.0 {
             let delta_sqrt = delta.sqrt();
-            solution.push(-k_dot_c_to_org - delta_sqrt);
+            solution.push((-half_b - delta_sqrt) / a);
             if delta > 0.0 {
-                solution.push(-k_dot_c_to_org + delta_sqrt);
+                solution.push((-half_b + delta_sqrt) / a);
             }
         }
         solution
     }
+
+    fn normal_at(&self, point: Point) -> Unit<Vector> {
+        Unit::new_normalize(point - self.c)
+    }
+
+    fn bounds(&self) -> Bounds {
+        let r = Vector::repeat(self.r);
+        Bounds {
+            min: self.c - r,
+            max: self.c + r,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -54,7 +70,10 @@ mod tests {
     use crate::geometry::ray::Ray;
     use crate::geometry::sphere::Sphere;
     use crate::geometry::{HitContext, Hittable, Shape};
+    use crate::prelude::*;
     use approx::assert_abs_diff_eq;
+    use nalgebra::{point, vector, Rotation3};
+    use std::f32::consts::{FRAC_1_SQRT_2, PI};
 
     #[test]
     fn test_intersect_sphere_tangent() {
@@ -88,4 +107,88 @@ mod tests {
         assert_abs_diff_eq!(t, -1.0);
         assert_eq!(obj_hit, &s as &dyn Hittable);
     }
+
+    #[test]
+    fn test_intersect_scaled_sphere() {
+        let r = Ray::new([0.0, 0.0, -5.0], [0.0, 0.0, 1.0]);
+        let s = Sphere::default().into_object().scaled(2.0, 2.0, 2.0);
+        let ts = s.intersect(r).all().map(|(t, _)| t).collect::<Vec<_>>();
+        assert_abs_diff_eq!(ts.as_slice(), [3.0, 7.0].as_ref());
+    }
+
+    #[test]
+    fn test_intersect_translated_sphere() {
+        let r = Ray::new([0.0, 0.0, -5.0], [0.0, 0.0, 1.0]);
+        let s = Sphere::default().into_object().translated(5.0, 0.0, 0.0);
+        assert_eq!(s.intersect(r).size(), 0);
+    }
+
+    #[test]
+    fn test_normal_on_axes() {
+        let s = Sphere::default();
+        assert_eq!(s.normal_at(point![1.0, 0.0, 0.0]), Vector::x_axis());
+        assert_eq!(s.normal_at(point![0.0, 1.0, 0.0]), Vector::y_axis());
+        assert_eq!(s.normal_at(point![0.0, 0.0, 1.0]), Vector::z_axis());
+        let v = 3f32.sqrt() / 3.0;
+        let n = s.normal_at(point![v, v, v]);
+        assert_abs_diff_eq!(n.into_inner(), vector![v, v, v], epsilon = 1e-6);
+        assert_abs_diff_eq!(n.norm(), 1.0);
+    }
+
+    #[test]
+    fn test_normal_off_center() {
+        let s = Sphere::new([1.0, 2.0, 3.0], 2.0);
+        assert_abs_diff_eq!(
+            s.normal_at(point![1.0, 2.0, 1.0]).into_inner(),
+            -Vector::z()
+        );
+    }
+
+    #[test]
+    fn test_normal_translated_sphere() {
+        let s = Sphere::default().into_object().translated(0.0, 1.0, 0.0);
+        let n = s.normal_at(point![0.0, 1.0 + FRAC_1_SQRT_2, -FRAC_1_SQRT_2]);
+        assert_abs_diff_eq!(
+            n.into_inner(),
+            vector![0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2],
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_normal_rotated_sphere() {
+        let rotation = Rotation3::from_axis_angle(&Vector::z_axis(), PI / 4.0);
+        let s = Sphere::default().into_object().rotated_z(PI / 4.0);
+        let p = rotation * point![1.0, 0.0, 0.0];
+        assert_abs_diff_eq!(
+            s.normal_at(p).into_inner(),
+            vector![FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0],
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_normal_scaled_and_rotated_sphere() {
+        let s = Sphere::default()
+            .into_object()
+            .rotated_z(PI / 5.0)
+            .scaled(1.0, 0.5, 1.0);
+        let v = 2f32.sqrt() / 2.0;
+        let n = s.normal_at(point![0.0, v, -v]);
+        assert_abs_diff_eq!(
+            n.into_inner(),
+            vector![0.0, 0.97014, -0.24254],
+            epsilon = 1e-5
+        );
+        assert_abs_diff_eq!(n.norm(), 1.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_normal_non_uniform_scale() {
+        // an ellipsoid stretched along x: the normal is not the radial direction
+        let s = Sphere::default().into_object().scaled(2.0, 1.0, 1.0);
+        let p = point![2.0 * FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0];
+        let expected = vector![FRAC_1_SQRT_2 / 2.0, FRAC_1_SQRT_2, 0.0].normalize();
+        assert_abs_diff_eq!(s.normal_at(p).into_inner(), expected, epsilon = 1e-6);
+    }
 }
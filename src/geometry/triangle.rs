@@ -0,0 +1,306 @@
+use crate::geometry::bounds::Bounds;
+use crate::geometry::ray::Ray;
+use crate::geometry::{Hit, HitData, Shape};
+use crate::prelude::*;
+use arrayvec::ArrayVec;
+use nalgebra::{Unit, Vector2};
+
+/// A flat triangle. Its normal follows the right-hand rule, pointing towards a
+/// viewer who sees `p1`, `p2`, `p3` counter-clockwise. Degenerate triangles,
+/// with no area, are never hit.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Triangle {
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    e1: Vector,
+    e2: Vector,
+    // `|e1 × e2|`, twice the area
+    twice_area: f32,
+    n: Unit<Vector>,
+}
+
+impl Triangle {
+    pub fn new(p1: impl Into<Point>, p2: impl Into<Point>, p3: impl Into<Point>) -> Self {
+        let (p1, p2, p3) = (p1.into(), p2.into(), p3.into());
+        let (e1, e2) = (p2 - p1, p3 - p1);
+        Self {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            twice_area: e1.cross(&e2).norm(),
+            n: face_normal(e1, e2),
+        }
+    }
+
+    pub fn vertices(&self) -> [Point; 3] {
+        [self.p1, self.p2, self.p3]
+    }
+
+    pub fn normal(&self) -> Unit<Vector> {
+        self.n
+    }
+
+    /// The weights of `p2` and `p3` in the barycentric coordinates of a point in
+    /// the plane of the triangle.
+    pub fn barycentric(&self, point: Point) -> Vector2<f32> {
+        let p = point - self.p1;
+        let (d11, d12, d22) = (
+            self.e1.dot(&self.e1),
+            self.e1.dot(&self.e2),
+            self.e2.dot(&self.e2),
+        );
+        let (dp1, dp2) = (p.dot(&self.e1), p.dot(&self.e2));
+        let denom = d11 * d22 - d12 * d12;
+        Vector2::new(d22 * dp1 - d12 * dp2, d11 * dp2 - d12 * dp1) / denom
+    }
+}
+
+impl Shape for Triangle {
+    type Hits = ArrayVec<Hit, 1>;
+
+    fn intersect(&self, Ray { orig, dir }: Ray) -> Self::Hits {
+        let mut solution = ArrayVec::new();
+        let hit = moller_trumbore(self.p1, self.e1, self.e2, self.twice_area, orig, dir);
+        if let Some((t, uv)) = hit {
+            solution.push(Hit {
+                t,
+                data: HitData {
+                    uv: Some(uv),
+                    ..HitData::default()
+                },
+            });
+        }
+        solution
+    }
+
+    fn normal_at(&self, _point: Point) -> Unit<Vector> {
+        self.n
+    }
+
+    fn bounds(&self) -> Bounds {
+        Bounds::from_points(self.vertices().iter().copied())
+    }
+}
+
+/// The normal of the triangle with edges `e1` and `e2`. A degenerate triangle
+/// has none, and gets an arbitrary one.
+pub(super) fn face_normal(e1: Vector, e2: Vector) -> Unit<Vector> {
+    Unit::try_new(e1.cross(&e2), 0.0).unwrap_or_else(Vector::z_axis)
+}
+
+/// Möller–Trumbore intersection of a ray with the triangle `p1`, `p1 + e1`,
+/// `p1 + e2`, giving the distance and the barycentric weights of the other two
+/// vertices. `twice_area` is `|e1 × e2|`.
+pub(super) fn moller_trumbore(
+    p1: Point,
+    e1: Vector,
+    e2: Vector,
+    twice_area: f32,
+    orig: Point,
+    dir: Vector,
+) -> Option<(f32, Vector2<f32>)> {
+    let dir_cross_e2 = dir.cross(&e2);
+    let det = e1.dot(&dir_cross_e2);
+    // relative to the size of the triangle and of `dir`, which need not be a
+    // unit vector. A degenerate triangle always has a zero determinant
+    if det.abs() <= 1e-8 * dir.norm() * twice_area {
+        return None;
+    }
+
+    let f = det.recip();
+    let p1_to_orig = orig - p1;
+    let u = f * p1_to_orig.dot(&dir_cross_e2);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let orig_cross_e1 = p1_to_orig.cross(&e1);
+    let v = f * dir.dot(&orig_cross_e1);
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    Some((f * e2.dot(&orig_cross_e1), Vector2::new(u, v)))
+}
+
+/// A triangle whose normal is interpolated between per-vertex normals.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SmoothTriangle {
+    pub triangle: Triangle,
+    pub normals: [Unit<Vector>; 3],
+}
+
+impl SmoothTriangle {
+    pub fn new(triangle: Triangle, n1: Vector, n2: Vector, n3: Vector) -> Self {
+        Self {
+            triangle,
+            normals: [
+                Unit::new_normalize(n1),
+                Unit::new_normalize(n2),
+                Unit::new_normalize(n3),
+            ],
+        }
+    }
+
+    fn interpolate(&self, uv: Vector2<f32>) -> Unit<Vector> {
+        let [n1, n2, n3] = self.normals;
+        Unit::new_normalize(
+            n1.into_inner() * (1.0 - uv.x - uv.y) + n2.into_inner() * uv.x + n3.into_inner() * uv.y,
+        )
+    }
+}
+
+impl Shape for SmoothTriangle {
+    type Hits = ArrayVec<Hit, 1>;
+
+    fn intersect(&self, ray: Ray) -> Self::Hits {
+        self.triangle.intersect(ray)
+    }
+
+    fn normal_at(&self, point: Point) -> Unit<Vector> {
+        self.interpolate(self.triangle.barycentric(point))
+    }
+
+    fn normal_at_hit(&self, point: Point, data: &HitData) -> Unit<Vector> {
+        match data.uv {
+            Some(uv) => self.interpolate(uv),
+            None => self.normal_at(point),
+        }
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.triangle.bounds()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::ray::Ray;
+    use crate::geometry::triangle::{SmoothTriangle, Triangle};
+    use crate::geometry::{HitData, Hittable, Shape};
+    use crate::prelude::*;
+    use approx::assert_abs_diff_eq;
+    use nalgebra::{point, vector, Vector2};
+    use std::f32::consts::FRAC_PI_2;
+
+    fn triangle() -> Triangle {
+        Triangle::new([0.0, 1.0, 0.0], [-1.0, 0.0, 0.0], [1.0, 0.0, 0.0])
+    }
+
+    fn smooth() -> SmoothTriangle {
+        SmoothTriangle::new(
+            triangle(),
+            vector![0.0, 1.0, 0.0],
+            vector![-1.0, 0.0, 0.0],
+            vector![1.0, 0.0, 0.0],
+        )
+    }
+
+    #[test]
+    fn test_construct() {
+        let t = triangle();
+        assert_eq!(t.e1, vector![-1.0, -1.0, 0.0]);
+        assert_eq!(t.e2, vector![1.0, -1.0, 0.0]);
+        // counter-clockwise seen from +z
+        assert_eq!(t.normal(), Vector::z_axis());
+        assert_eq!(t.normal_at(point![-0.5, 0.75, 0.0]), Vector::z_axis());
+    }
+
+    #[test]
+    fn test_parallel_ray() {
+        let r = Ray::new([0.0, -1.0, -2.0], [0.0, 1.0, 0.0]);
+        assert!(triangle().intersect(r).is_empty());
+    }
+
+    #[test]
+    fn test_miss_edges() {
+        for &orig in [[1.0, 1.0, -2.0], [-1.0, 1.0, -2.0], [0.0, -1.0, -2.0]].iter() {
+            let r = Ray::new(orig, [0.0, 0.0, 1.0]);
+            assert!(triangle().intersect(r).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_hit() {
+        let r = Ray::new([0.0, 0.5, -2.0], [0.0, 0.0, 1.0]);
+        let hits = triangle().intersect(r);
+        assert_eq!(hits.len(), 1);
+        assert_abs_diff_eq!(hits[0].t, 2.0);
+        assert_abs_diff_eq!(hits[0].data.uv.unwrap(), Vector2::new(0.25, 0.25));
+    }
+
+    #[test]
+    fn test_smooth_uv() {
+        let r = Ray::new([-0.2, 0.3, -2.0], [0.0, 0.0, 1.0]);
+        let uv = smooth().intersect(r)[0].data.uv.unwrap();
+        assert_abs_diff_eq!(uv, Vector2::new(0.45, 0.25), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_smooth_normal() {
+        let data = HitData {
+            uv: Some(Vector2::new(0.45, 0.25)),
+            ..HitData::default()
+        };
+        let n = smooth().normal_at_hit(Point::origin(), &data);
+        assert_abs_diff_eq!(
+            n.into_inner(),
+            vector![-0.5547, 0.83205, 0.0],
+            epsilon = 1e-5
+        );
+        // without hit data the coordinates are recovered from the point
+        let n = smooth().normal_at(point![-0.2, 0.3, 0.0]);
+        assert_abs_diff_eq!(
+            n.into_inner(),
+            vector![-0.5547, 0.83205, 0.0],
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn test_hit_context_carries_uv() {
+        let obj = smooth().into_object().rotated_y(FRAC_PI_2);
+        let r = Ray::new([-2.0, 0.3, 0.2], [1.0, 0.0, 0.0]);
+        let inter = obj.intersect(r);
+        let (t, ctx) = inter.hit().unwrap();
+        assert_abs_diff_eq!(t, 2.0, epsilon = 1e-6);
+        assert_abs_diff_eq!(
+            ctx.data.uv.unwrap(),
+            Vector2::new(0.45, 0.25),
+            epsilon = 1e-6
+        );
+        assert_abs_diff_eq!(
+            ctx.normal_at(r.trace(t)).into_inner(),
+            vector![0.0, 0.83205, 0.5547],
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn test_millimetre_triangle() {
+        let t = Triangle::new([0.0, 0.001, 0.0], [-0.001, 0.0, 0.0], [0.001, 0.0, 0.0]);
+        let r = Ray::new([0.0, 0.0005, -2.0], [0.0, 0.0, 1.0]);
+        assert_abs_diff_eq!(t.intersect(r)[0].t, 2.0);
+        // a direction shortened by a transform
+        let r = Ray {
+            orig: point![0.0, 0.0005, -2.0],
+            dir: vector![0.0, 0.0, 0.001],
+        };
+        assert_abs_diff_eq!(t.intersect(r)[0].t, 2000.0, epsilon = 1e-3);
+        let r = Ray::new([0.0, 0.0005, -2.0], [0.0, 1.0, 0.0]);
+        assert!(t.intersect(r).is_empty());
+    }
+
+    #[test]
+    fn test_degenerate() {
+        let t = Triangle::new([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [2.0, 0.0, 0.0]);
+        assert_abs_diff_eq!(t.normal().norm(), 1.0);
+        let r = Ray::new([1.0, 0.0, -2.0], [0.0, 0.0, 1.0]);
+        assert!(t.intersect(r).is_empty());
+        let t = Triangle::new([1.0, 1.0, 1.0], [1.0, 1.0, 1.0], [1.0, 1.0, 1.0]);
+        assert_abs_diff_eq!(t.normal().norm(), 1.0);
+        let r = Ray::new([1.0, 1.0, -2.0], [0.0, 0.0, 1.0]);
+        assert!(t.intersect(r).is_empty());
+    }
+}
@@ -1,5 +1,7 @@
+pub mod camera;
 pub mod canvas;
 pub mod color;
 pub mod geometry;
+pub mod loader;
 pub mod prelude;
 pub mod util;
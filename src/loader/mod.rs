@@ -0,0 +1,45 @@
+pub use mtl::{parse_mtl, Material, MaterialLibrary};
+pub use obj::{parse_obj, Obj, ObjGroup};
+pub use ply::parse_ply;
+pub use stl::parse_stl;
+
+mod mtl;
+mod obj;
+mod ply;
+mod stl;
+
+use std::io;
+use std::str::FromStr;
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn invalid_line(line: usize, msg: &str) -> io::Error {
+    invalid_data(&format!("line {}: {}", line, msg))
+}
+
+/// Strips a trailing `#` comment and splits the rest of the line into its keyword
+/// and arguments.
+fn split_line(line: &str) -> Option<(&str, impl Iterator<Item = &str> + Clone)> {
+    let line = line.split('#').next().unwrap_or_default();
+    let mut words = line.split_whitespace();
+    words.next().map(|keyword| (keyword, words))
+}
+
+fn parse_floats<'a, const N: usize>(
+    line: usize,
+    args: impl Iterator<Item = &'a str>,
+    required: usize,
+) -> io::Result<[f32; N]> {
+    let mut values = [0.0; N];
+    let mut count = 0;
+    for (value, arg) in values.iter_mut().zip(args) {
+        *value = f32::from_str(arg).map_err(|_| invalid_line(line, "invalid number"))?;
+        count += 1;
+    }
+    if count < required {
+        return Err(invalid_line(line, "missing values"));
+    }
+    Ok(values)
+}
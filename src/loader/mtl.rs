@@ -0,0 +1,143 @@
+use crate::color::Color;
+use crate::loader::{invalid_line, parse_floats, split_line};
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+
+/// A material from a Wavefront `.mtl` library.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    pub name: String,
+    /// `Ka`
+    pub ambient: Color,
+    /// `Kd`
+    pub diffuse: Color,
+    /// `Ks`
+    pub specular: Color,
+    /// `Ns`
+    pub shininess: f32,
+    /// `d`, or `1 - Tr`
+    pub opacity: f32,
+    /// `Ni`
+    pub refractive_index: f32,
+    /// `map_Kd`
+    pub diffuse_map: Option<String>,
+}
+
+impl Material {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ambient: Color::default(),
+            diffuse: Color::new([1.0, 1.0, 1.0]),
+            specular: Color::default(),
+            shininess: 0.0,
+            opacity: 1.0,
+            refractive_index: 1.0,
+            diffuse_map: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaterialLibrary {
+    pub materials: HashMap<String, Material>,
+    /// The number of unsupported statements that were skipped.
+    pub ignored: usize,
+}
+
+pub fn parse_mtl<R: BufRead>(r: R) -> io::Result<MaterialLibrary> {
+    let mut lib = MaterialLibrary::default();
+    let mut current: Option<Material> = None;
+
+    for (i, line) in r.lines().enumerate() {
+        let line = line?;
+        let n = i + 1;
+        let (keyword, args) = match split_line(&line) {
+            Some(split) => split,
+            None => continue,
+        };
+
+        if keyword == "newmtl" {
+            let name = args.collect::<Vec<_>>().join(" ");
+            if let Some(done) = current.replace(Material::new(name)) {
+                lib.materials.insert(done.name.clone(), done);
+            }
+            continue;
+        }
+        let material = match current.as_mut() {
+            Some(material) => material,
+            None => return Err(invalid_line(n, "material property before `newmtl`")),
+        };
+        let color = |args| parse_floats::<3>(n, args, 3).map(Color::new);
+        let scalar = |args| parse_floats::<1>(n, args, 1).map(|[v]| v);
+        match keyword {
+            "Ka" => material.ambient = color(args)?,
+            "Kd" => material.diffuse = color(args)?,
+            "Ks" => material.specular = color(args)?,
+            "Ns" => material.shininess = scalar(args)?,
+            "d" => material.opacity = scalar(args)?,
+            "Tr" => material.opacity = 1.0 - scalar(args)?,
+            "Ni" => material.refractive_index = scalar(args)?,
+            // options such as `-bm` come before the file name
+            "map_Kd" => material.diffuse_map = args.last().map(str::to_owned),
+            _ => lib.ignored += 1,
+        }
+    }
+
+    if let Some(done) = current {
+        lib.materials.insert(done.name.clone(), done);
+    }
+    Ok(lib)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::loader::parse_mtl;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_parse_mtl() {
+        let src = "\
+# two materials
+newmtl red
+Ka 0.1 0 0
+Kd 0.8 0.1 0.1
+Ks 0.5 0.5 0.5
+Ns 250
+d 0.9
+illum 2
+
+newmtl glass
+Tr 0.75
+Ni 1.5
+map_Kd -bm 1 textures/glass.png
+";
+        let lib = parse_mtl(src.as_bytes()).unwrap();
+        assert_eq!(lib.materials.len(), 2);
+        assert_eq!(lib.ignored, 1);
+
+        let red = &lib.materials["red"];
+        assert_eq!(red.ambient, Color::new([0.1, 0.0, 0.0]));
+        assert_eq!(red.diffuse, Color::new([0.8, 0.1, 0.1]));
+        assert_eq!(red.specular, Color::new([0.5, 0.5, 0.5]));
+        assert_eq!(red.shininess, 250.0);
+        assert_eq!(red.opacity, 0.9);
+
+        let glass = &lib.materials["glass"];
+        assert_eq!(glass.opacity, 0.25);
+        assert_eq!(glass.refractive_index, 1.5);
+        assert_eq!(glass.diffuse_map.as_deref(), Some("textures/glass.png"));
+        assert_eq!(glass.diffuse, Color::new([1.0, 1.0, 1.0]));
+    }
+
+    #[test]
+    fn test_parse_mtl_errors() {
+        let err = parse_mtl("Kd 1 1 1\n".as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let err = parse_mtl("newmtl a\nKd 1 x 1\n".as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: invalid number");
+        let err = parse_mtl("newmtl a\nKd 1 1\n".as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: missing values");
+    }
+}
@@ -0,0 +1,372 @@
+use crate::geometry::triangle::{SmoothTriangle, Triangle};
+use crate::geometry::{Hittable, Shape};
+use crate::loader::{invalid_line, parse_floats, parse_mtl, split_line, Material};
+use crate::prelude::*;
+use nalgebra::Vector2;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+const DEFAULT_GROUP: &str = "default";
+
+/// A parsed Wavefront `.obj` file. Faces become triangles, smooth ones when every
+/// vertex of the face has a normal.
+#[derive(Debug, Default)]
+pub struct Obj {
+    pub vertices: Vec<Point>,
+    pub normals: Vec<Vector>,
+    pub tex_coords: Vec<Vector2<f32>>,
+    pub groups: Vec<ObjGroup>,
+    /// The `.mtl` files named by `mtllib` statements.
+    pub material_libs: Vec<String>,
+    /// Materials loaded from `material_libs` by [`Obj::open`].
+    pub materials: HashMap<String, Material>,
+    /// The number of unsupported statements that were skipped.
+    pub ignored: usize,
+}
+
+/// The faces of a `g` or `o` statement sharing one `usemtl` material.
+#[derive(Debug)]
+pub struct ObjGroup {
+    pub name: String,
+    pub material: Option<String>,
+    pub objects: Vec<Box<dyn Hittable>>,
+}
+
+impl ObjGroup {
+    fn new(name: String, material: Option<String>) -> Self {
+        Self {
+            name,
+            material,
+            objects: Vec::new(),
+        }
+    }
+}
+
+impl Obj {
+    /// Reads an `.obj` file along with the material libraries it references,
+    /// which are looked up relative to it.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Obj> {
+        let path = path.as_ref();
+        let mut obj = parse_obj(BufReader::new(File::open(path)?))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for lib in obj.material_libs.iter() {
+            let lib = parse_mtl(BufReader::new(File::open(dir.join(lib))?))?;
+            obj.materials.extend(lib.materials);
+            obj.ignored += lib.ignored;
+        }
+        Ok(obj)
+    }
+
+    pub fn group(&self, name: &str) -> Option<&ObjGroup> {
+        self.groups.iter().find(|g| g.name == name)
+    }
+
+    /// Moves the triangles of every group out of the file.
+    pub fn into_objects(self) -> impl Iterator<Item = Box<dyn Hittable>> {
+        self.groups.into_iter().flat_map(|g| g.objects)
+    }
+}
+
+/// `v`, `v/vt`, `v//vn` or `v/vt/vn`, resolved to 0-based indices.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct FaceVertex {
+    v: usize,
+    vt: Option<usize>,
+    vn: Option<usize>,
+}
+
+// indices are 1-based, negative ones count back from the last element
+fn resolve_index(line: usize, index: &str, len: usize) -> io::Result<usize> {
+    let index = index
+        .parse::<isize>()
+        .map_err(|_| invalid_line(line, "invalid index"))?;
+    let resolved = if index < 0 {
+        len as isize + index
+    } else {
+        index - 1
+    };
+    if resolved < 0 || resolved as usize >= len {
+        return Err(invalid_line(line, "index out of range"));
+    }
+    Ok(resolved as usize)
+}
+
+impl FaceVertex {
+    fn parse(line: usize, s: &str, obj: &Obj) -> io::Result<Self> {
+        let mut parts = s.split('/');
+        let v = resolve_index(line, parts.next().unwrap_or_default(), obj.vertices.len())?;
+        let mut optional = |len| match parts.next() {
+            None | Some("") => Ok(None),
+            Some(index) => resolve_index(line, index, len).map(Some),
+        };
+        let vt = optional(obj.tex_coords.len())?;
+        let vn = optional(obj.normals.len())?;
+        Ok(FaceVertex { v, vt, vn })
+    }
+}
+
+pub fn parse_obj<R: BufRead>(r: R) -> io::Result<Obj> {
+    let mut obj = Obj::default();
+    let mut group = ObjGroup::new(DEFAULT_GROUP.to_owned(), None);
+
+    for (i, line) in r.lines().enumerate() {
+        let line = line?;
+        let n = i + 1;
+        let (keyword, args) = match split_line(&line) {
+            Some(split) => split,
+            None => continue,
+        };
+
+        match keyword {
+            "v" => obj.vertices.push(parse_floats::<3>(n, args, 3)?.into()),
+            "vn" => obj.normals.push(parse_floats::<3>(n, args, 3)?.into()),
+            "vt" => obj
+                .tex_coords
+                .push(Vector2::from(parse_floats::<2>(n, args, 1)?)),
+            "f" => {
+                let face = args
+                    .map(|arg| FaceVertex::parse(n, arg, &obj))
+                    .collect::<io::Result<Vec<_>>>()?;
+                if face.len() < 3 {
+                    return Err(invalid_line(n, "a face needs at least 3 vertices"));
+                }
+                // fan triangulation, which is exact for convex polygons
+                for pair in face[1..].windows(2) {
+                    group
+                        .objects
+                        .push(triangle(&obj, [face[0], pair[0], pair[1]]));
+                }
+            }
+            "g" | "o" => {
+                let name = args.collect::<Vec<_>>().join(" ");
+                let name = if name.is_empty() {
+                    DEFAULT_GROUP.to_owned()
+                } else {
+                    name
+                };
+                let material = group.material.clone();
+                finish_group(&mut obj, &mut group, ObjGroup::new(name, material));
+            }
+            "usemtl" => {
+                let material = Some(args.collect::<Vec<_>>().join(" "));
+                if group.objects.is_empty() {
+                    group.material = material;
+                } else {
+                    let name = group.name.clone();
+                    finish_group(&mut obj, &mut group, ObjGroup::new(name, material));
+                }
+            }
+            "mtllib" => obj.material_libs.extend(args.map(str::to_owned)),
+            _ => obj.ignored += 1,
+        }
+    }
+
+    finish_group(&mut obj, &mut group, ObjGroup::new(String::new(), None));
+    Ok(obj)
+}
+
+fn finish_group(obj: &mut Obj, group: &mut ObjGroup, next: ObjGroup) {
+    let done = std::mem::replace(group, next);
+    if !done.objects.is_empty() {
+        obj.groups.push(done);
+    }
+}
+
+fn triangle(obj: &Obj, [a, b, c]: [FaceVertex; 3]) -> Box<dyn Hittable> {
+    let flat = Triangle::new(obj.vertices[a.v], obj.vertices[b.v], obj.vertices[c.v]);
+    match (a.vn, b.vn, c.vn) {
+        (Some(na), Some(nb), Some(nc)) => Box::new(
+            SmoothTriangle::new(flat, obj.normals[na], obj.normals[nb], obj.normals[nc])
+                .into_object(),
+        ),
+        _ => Box::new(flat.into_object()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::ray::Ray;
+    use crate::loader::{parse_obj, Obj};
+    use crate::prelude::*;
+    use approx::assert_abs_diff_eq;
+    use nalgebra::{point, vector, Vector2};
+    use std::env;
+    use std::fs;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_ignores_unrecognized_lines() {
+        let src = "\
+There was a young lady named Bright
+who traveled much faster than light.
+She set out one day
+in a relative way,
+and came back the previous night.
+";
+        let obj = parse_obj(src.as_bytes()).unwrap();
+        assert_eq!(obj.ignored, 5);
+        assert!(obj.groups.is_empty());
+    }
+
+    #[test]
+    fn test_vertex_records() {
+        let src = "\
+v -1 1 0
+v -1.0000 0.5000 0.0000
+v 1 0 0  # a comment
+vn 0 0 1
+vt 0.5 0.25
+vt 0.75
+s off
+";
+        let obj = parse_obj(src.as_bytes()).unwrap();
+        assert_eq!(
+            obj.vertices,
+            [
+                point![-1.0, 1.0, 0.0],
+                point![-1.0, 0.5, 0.0],
+                point![1.0, 0.0, 0.0]
+            ]
+        );
+        assert_eq!(obj.normals, [vector![0.0, 0.0, 1.0]]);
+        assert_eq!(
+            obj.tex_coords,
+            [Vector2::new(0.5, 0.25), Vector2::new(0.75, 0.0)]
+        );
+        assert_eq!(obj.ignored, 1);
+    }
+
+    #[test]
+    fn test_fan_triangulation() {
+        let src = "\
+v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+v 0 2 0
+f 1 2 3 4 5
+";
+        let obj = parse_obj(src.as_bytes()).unwrap();
+        assert_eq!(obj.groups.len(), 1);
+        assert_eq!(obj.groups[0].name, "default");
+        assert_eq!(obj.groups[0].objects.len(), 3);
+
+        // the third triangle (1, 4, 5) covers the tip of the pentagon
+        let r = Ray::new([0.0, 1.5, 5.0], [0.0, 0.0, -1.0]);
+        let hits = obj.groups[0]
+            .objects
+            .iter()
+            .filter(|o| o.intersect(r).hit().is_some())
+            .count();
+        assert_eq!(hits, 1);
+    }
+
+    #[test]
+    fn test_index_forms() {
+        let src = "\
+v 0 1 0
+v -1 0 0
+v 1 0 0
+vt 0 0
+vn -1 0 0
+vn 1 0 0
+vn 0 1 0
+f 1//3 2//1 3//2
+f 1/1/3 2/1/1 3/1/2
+f -3/-1 -2/1 -1/1
+";
+        let obj = parse_obj(src.as_bytes()).unwrap();
+        let objects = &obj.groups[0].objects;
+        assert_eq!(objects.len(), 3);
+
+        let r = Ray::new([-0.2, 0.3, 2.0], [0.0, 0.0, -1.0]);
+        let p = r.trace(2.0);
+        let smooth = objects[0].intersect(r);
+        let (_, ctx) = smooth.hit().unwrap();
+        assert_abs_diff_eq!(
+            ctx.normal_at(p).into_inner(),
+            vector![-0.5547, 0.83205, 0.0],
+            epsilon = 1e-5
+        );
+        let flat = objects[2].intersect(r);
+        let (_, ctx) = flat.hit().unwrap();
+        assert_eq!(ctx.normal_at(p), Vector::z_axis());
+    }
+
+    #[test]
+    fn test_groups_and_materials() {
+        let src = "\
+mtllib scene.mtl
+v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+f 1 2 3
+g FirstGroup
+usemtl red
+f 1 2 3
+usemtl blue
+f 1 3 4
+o Second Group
+f 1 3 4
+g
+";
+        let obj = parse_obj(src.as_bytes()).unwrap();
+        assert_eq!(obj.material_libs, ["scene.mtl"]);
+        let summary = obj
+            .groups
+            .iter()
+            .map(|g| (g.name.as_str(), g.material.as_deref(), g.objects.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("default", None, 1),
+                ("FirstGroup", Some("red"), 1),
+                ("FirstGroup", Some("blue"), 1),
+                ("Second Group", Some("blue"), 1),
+            ]
+        );
+        assert_eq!(
+            obj.group("FirstGroup").unwrap().material.as_deref(),
+            Some("red")
+        );
+        assert_eq!(obj.into_objects().count(), 4);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = parse_obj("v 1 2\n".as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let err = parse_obj("v 0 0 0\nv 1 0 0\nf 1 2 3\n".as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "line 3: index out of range");
+        let err = parse_obj("v 0 0 0\nv 1 0 0\nf 1 2\n".as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "line 3: a face needs at least 3 vertices");
+        let err = parse_obj("v 0 0 0\nf 1 1/x 1\n".as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: invalid index");
+    }
+
+    #[test]
+    fn test_open_with_materials() {
+        let dir = env::temp_dir().join(format!("ray-tracing-obj-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("tri.obj"),
+            "mtllib tri.mtl\nv 0 1 0\nv -1 0 0\nv 1 0 0\nusemtl matte\nf 1 2 3\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("tri.mtl"),
+            "newmtl matte\nKd 0.2 0.4 0.6\nillum 1\n",
+        )
+        .unwrap();
+
+        let obj = Obj::open(dir.join("tri.obj")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        let material = &obj.materials[obj.groups[0].material.as_deref().unwrap()];
+        assert_abs_diff_eq!(*material.diffuse, vector![0.2, 0.4, 0.6]);
+        assert_eq!(obj.ignored, 1);
+    }
+}
@@ -0,0 +1,473 @@
+use crate::color::{Color, ColorSpace};
+use crate::geometry::mesh::Mesh;
+use crate::loader::{invalid_data, invalid_line};
+use crate::prelude::*;
+use nalgebra::Vector2;
+use std::convert::TryInto;
+use std::io::{self, BufRead};
+use std::str;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(line: usize, name: &str) -> io::Result<Self> {
+        Ok(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return Err(invalid_line(line, "unknown property type")),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    /// The value an unsigned sample takes at full intensity, for colors.
+    fn max(self) -> f64 {
+        match self {
+            Scalar::U8 => u8::MAX.into(),
+            Scalar::U16 => u16::MAX.into(),
+            _ => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Property {
+    Scalar(String, Scalar),
+    List(String, Scalar, Scalar),
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Property::Scalar(name, _) | Property::List(name, ..) => name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Reads an ASCII or binary (either endianness) PLY file into a mesh. Vertex
+/// normals (`nx`, `ny`, `nz`), texture coordinates (`u`/`v` or `s`/`t`) and colors
+/// (`red`, `green`, `blue`) are kept when present; integer colors are normalized
+/// to `[0, 1]` and decoded from `space`. Polygons are fan triangulated.
+pub fn parse_ply<R: BufRead>(mut r: R, space: ColorSpace) -> io::Result<Mesh> {
+    let (format, elements) = parse_header(&mut r)?;
+    let mut body = Vec::new();
+    r.read_to_end(&mut body)?;
+    let mut values = Values {
+        format,
+        data: &body,
+        pos: 0,
+    };
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut colors = Vec::new();
+    let mut indices = Vec::new();
+    for element in elements.iter() {
+        let column = |names: &[&str]| {
+            element
+                .properties
+                .iter()
+                .position(|p| names.contains(&p.name()))
+        };
+        let columns =
+            |names: [&[&str]; 3]| match (column(names[0]), column(names[1]), column(names[2])) {
+                (Some(a), Some(b), Some(c)) => Some([a, b, c]),
+                _ => None,
+            };
+        let xyz = columns([&["x"], &["y"], &["z"]]);
+        let normal = columns([&["nx"], &["ny"], &["nz"]]);
+        let uv = match (
+            column(&["u", "s", "texture_u", "texture_s"]),
+            column(&["v", "t", "texture_v", "texture_t"]),
+        ) {
+            (Some(u), Some(v)) => Some([u, v]),
+            _ => None,
+        };
+        let rgb = columns([&["red", "r"], &["green", "g"], &["blue", "b"]]);
+        let face = element
+            .properties
+            .iter()
+            .position(|p| matches!(p, Property::List(name, ..) if name == "vertex_indices" || name == "vertex_index"));
+
+        for _ in 0..element.count {
+            let mut row = Vec::with_capacity(element.properties.len());
+            let mut list = Vec::new();
+            for (i, property) in element.properties.iter().enumerate() {
+                match *property {
+                    Property::Scalar(_, ty) => row.push(values.next(ty)?),
+                    Property::List(_, count_ty, item_ty) => {
+                        row.push(0.0);
+                        let count = values.next(count_ty)? as usize;
+                        for _ in 0..count {
+                            let item = values.next(item_ty)?;
+                            if Some(i) == face {
+                                list.push(item);
+                            }
+                        }
+                    }
+                }
+            }
+
+            if element.name == "vertex" {
+                let vector = |[a, b, c]: [usize; 3]| {
+                    Vector::new(row[a] as f32, row[b] as f32, row[c] as f32)
+                };
+                let xyz = xyz.ok_or_else(|| invalid_data("vertices need x, y and z"))?;
+                positions.push(Point::from(vector(xyz)));
+                if let Some(normal) = normal {
+                    normals.push(vector(normal));
+                }
+                if let Some([u, v]) = uv {
+                    uvs.push(Vector2::new(row[u] as f32, row[v] as f32));
+                }
+                if let Some(rgb) = rgb {
+                    let channel = |i: usize| {
+                        let ty = match element.properties[i] {
+                            Property::Scalar(_, ty) => ty,
+                            Property::List(..) => Scalar::F32,
+                        };
+                        (row[i] / ty.max()) as f32
+                    };
+                    colors.push(space.decode(Color::new([
+                        channel(rgb[0]),
+                        channel(rgb[1]),
+                        channel(rgb[2]),
+                    ])));
+                }
+            } else if element.name == "face" && face.is_some() {
+                if list.len() < 3 {
+                    return Err(invalid_data("a face needs at least 3 vertices"));
+                }
+                if list.iter().any(|&i| i < 0.0 || i.fract() != 0.0) {
+                    return Err(invalid_data("invalid vertex index"));
+                }
+                let list = list.into_iter().map(|i| i as u32).collect::<Vec<_>>();
+                for pair in list[1..].windows(2) {
+                    indices.push([list[0], pair[0], pair[1]]);
+                }
+            }
+        }
+    }
+
+    if indices
+        .iter()
+        .flatten()
+        .any(|&i| i as usize >= positions.len())
+    {
+        return Err(invalid_data("vertex index out of range"));
+    }
+    let mut mesh = Mesh::new(positions, indices);
+    if !normals.is_empty() {
+        mesh = mesh.with_normals(normals);
+    }
+    if !uvs.is_empty() {
+        mesh = mesh.with_uvs(uvs);
+    }
+    if !colors.is_empty() {
+        mesh = mesh.with_colors(colors);
+    }
+    Ok(mesh)
+}
+
+fn parse_header<R: BufRead>(r: &mut R) -> io::Result<(Format, Vec<Element>)> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut line = String::new();
+    for n in 1.. {
+        line.clear();
+        if r.read_line(&mut line)? == 0 {
+            return Err(invalid_data("missing `end_header`"));
+        }
+        let mut words = line.split_whitespace();
+        let keyword = words.next().unwrap_or_default();
+        if n == 1 {
+            if keyword != "ply" {
+                return Err(invalid_data("not a PLY file"));
+            }
+            continue;
+        }
+        match keyword {
+            "format" => {
+                format = Some(match words.next() {
+                    Some("ascii") => Format::Ascii,
+                    Some("binary_little_endian") => Format::BinaryLittleEndian,
+                    Some("binary_big_endian") => Format::BinaryBigEndian,
+                    _ => return Err(invalid_line(n, "unknown format")),
+                });
+            }
+            "element" => {
+                let name = words.next().unwrap_or_default().to_owned();
+                let count = words
+                    .next()
+                    .and_then(|c| c.parse().ok())
+                    .ok_or_else(|| invalid_line(n, "invalid element count"))?;
+                elements.push(Element {
+                    name,
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            "property" => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| invalid_line(n, "property before `element`"))?;
+                let words = words.collect::<Vec<_>>();
+                let property = match *words.as_slice() {
+                    ["list", count, item, name] => Property::List(
+                        name.to_owned(),
+                        Scalar::parse(n, count)?,
+                        Scalar::parse(n, item)?,
+                    ),
+                    [ty, name] => Property::Scalar(name.to_owned(), Scalar::parse(n, ty)?),
+                    _ => return Err(invalid_line(n, "invalid property")),
+                };
+                element.properties.push(property);
+            }
+            "end_header" => break,
+            "comment" | "obj_info" | "" => {}
+            _ => return Err(invalid_line(n, "unexpected header statement")),
+        }
+    }
+    let format = format.ok_or_else(|| invalid_data("missing `format`"))?;
+    Ok((format, elements))
+}
+
+struct Values<'a> {
+    format: Format,
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Values<'_> {
+    fn next(&mut self, ty: Scalar) -> io::Result<f64> {
+        if self.format == Format::Ascii {
+            return self
+                .next_token()?
+                .parse::<f64>()
+                .map_err(|_| invalid_data("invalid number"));
+        }
+
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + ty.size())
+            .ok_or_else(|| invalid_data("unexpected end of data"))?;
+        self.pos += ty.size();
+        let little = self.format == Format::BinaryLittleEndian;
+        macro_rules! read {
+            ($t:ty) => {{
+                let bytes = bytes.try_into().unwrap();
+                let value = if little {
+                    <$t>::from_le_bytes(bytes)
+                } else {
+                    <$t>::from_be_bytes(bytes)
+                };
+                value as f64
+            }};
+        }
+        Ok(match ty {
+            Scalar::I8 => read!(i8),
+            Scalar::U8 => read!(u8),
+            Scalar::I16 => read!(i16),
+            Scalar::U16 => read!(u16),
+            Scalar::I32 => read!(i32),
+            Scalar::U32 => read!(u32),
+            Scalar::F32 => read!(f32),
+            Scalar::F64 => read!(f64),
+        })
+    }
+
+    fn next_token(&mut self) -> io::Result<&str> {
+        let rest = &self.data[self.pos..];
+        let start = rest
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .ok_or_else(|| invalid_data("unexpected end of data"))?;
+        let len = rest[start..]
+            .iter()
+            .position(|b| b.is_ascii_whitespace())
+            .unwrap_or(rest.len() - start);
+        self.pos += start + len;
+        str::from_utf8(&rest[start..start + len]).map_err(|_| invalid_data("invalid number"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::{Color, ColorSpace};
+    use crate::geometry::ray::Ray;
+    use crate::geometry::Shape;
+    use crate::loader::parse_ply;
+    use crate::prelude::*;
+    use approx::assert_abs_diff_eq;
+    use nalgebra::{point, Vector2};
+    use std::io::ErrorKind;
+
+    const ASCII: &str = "\
+ply
+format ascii 1.0
+comment a square with a red and a blue corner
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element edge 1
+property int vertex1
+property int vertex2
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 0 0 255
+1 1 0 0 0 255
+0 1 0 255 0 0
+0 2
+4 0 1 2 3
+";
+
+    fn binary_header(format: &str) -> Vec<u8> {
+        format!(
+            "ply\nformat {} 1.0\nelement vertex 3\nproperty float x\nproperty float y\n\
+             property float z\nproperty float nx\nproperty float ny\nproperty float nz\n\
+             property float s\nproperty float t\nelement face 1\n\
+             property list uchar uint vertex_index\nend_header\n",
+            format
+        )
+        .into_bytes()
+    }
+
+    const VERTICES: [[f32; 8]; 3] = [
+        [0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.5, 1.0],
+        [-1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0],
+    ];
+
+    #[test]
+    fn test_ascii_with_colors() {
+        let mesh = parse_ply(ASCII.as_bytes(), ColorSpace::Srgb).unwrap();
+        assert_eq!(mesh.positions().len(), 4);
+        assert_eq!(mesh.indices(), [[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(mesh.positions()[2], point![1.0, 1.0, 0.0]);
+        assert_eq!(mesh.colors()[0], Color::new([1.0, 0.0, 0.0]));
+
+        // halfway along the bottom edge the colors blend
+        let r = Ray::new([0.5, 0.0, 1.0], [0.0, 0.0, -1.0]);
+        let hit = mesh.intersect(r)[0];
+        let color = mesh.color_at(&hit.data).unwrap();
+        assert_abs_diff_eq!(color, Color::new([0.5, 0.0, 0.5]), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_color_space() {
+        let srgb = parse_ply(
+            ASCII.replace("255 0 0", "128 0 0").as_bytes(),
+            ColorSpace::Srgb,
+        );
+        let linear = parse_ply(
+            ASCII.replace("255 0 0", "128 0 0").as_bytes(),
+            ColorSpace::Linear,
+        );
+        assert_abs_diff_eq!(srgb.unwrap().colors()[0].x, 0.215_861, epsilon = 1e-5);
+        assert_abs_diff_eq!(linear.unwrap().colors()[0].x, 128.0 / 255.0);
+    }
+
+    #[test]
+    fn test_binary_endianness() {
+        let mut little = binary_header("binary_little_endian");
+        let mut big = binary_header("binary_big_endian");
+        for v in VERTICES.iter().flatten() {
+            little.extend_from_slice(&v.to_le_bytes());
+            big.extend_from_slice(&v.to_be_bytes());
+        }
+        little.push(3);
+        big.push(3);
+        for i in 0u32..3 {
+            little.extend_from_slice(&i.to_le_bytes());
+            big.extend_from_slice(&i.to_be_bytes());
+        }
+
+        let mesh = parse_ply(little.as_slice(), ColorSpace::Srgb).unwrap();
+        assert_eq!(mesh, parse_ply(big.as_slice(), ColorSpace::Srgb).unwrap());
+        assert_eq!(mesh.positions()[1], point![-1.0, 0.0, 0.0]);
+        assert_eq!(mesh.normals()[2], Vector::z());
+        assert_eq!(mesh.uvs()[0], Vector2::new(0.5, 1.0));
+        assert!(mesh.colors().is_empty());
+        assert_eq!(mesh.indices(), [[0, 1, 2]]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = parse_ply("plx\n".as_bytes(), ColorSpace::Srgb).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let err = parse_ply(
+            ASCII.replace("uchar red", "quad red").as_bytes(),
+            ColorSpace::Srgb,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "line 8: unknown property type");
+        let err = parse_ply(
+            ASCII.replace("4 0 1 2 3", "4 0 1 2 7").as_bytes(),
+            ColorSpace::Srgb,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "vertex index out of range");
+        for face in ["4 0 1 2 -1", "4 0 1 2.7 3"] {
+            let err = parse_ply(
+                ASCII.replace("4 0 1 2 3", face).as_bytes(),
+                ColorSpace::Srgb,
+            )
+            .unwrap_err();
+            assert_eq!(err.to_string(), "invalid vertex index");
+        }
+        let err = parse_ply(
+            ASCII.replace("4 0 1 2 3", "4 0 1 2").as_bytes(),
+            ColorSpace::Srgb,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "unexpected end of data");
+        let mut truncated = binary_header("binary_little_endian");
+        truncated.extend_from_slice(&[0; 10]);
+        let err = parse_ply(truncated.as_slice(), ColorSpace::Srgb).unwrap_err();
+        assert_eq!(err.to_string(), "unexpected end of data");
+    }
+}
@@ -0,0 +1,175 @@
+use crate::geometry::mesh::Mesh;
+use crate::loader::{invalid_data, invalid_line, parse_floats, split_line};
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io::{self, Read};
+use std::str;
+
+const HEADER_LEN: usize = 80;
+const FACET_LEN: usize = 50;
+
+/// Reads a binary or ASCII STL file. Vertices shared between facets are merged,
+/// and facet normals are ignored in favour of the winding order of the vertices.
+pub fn parse_stl<R: Read>(mut r: R) -> io::Result<Mesh> {
+    let mut data = Vec::new();
+    r.read_to_end(&mut data)?;
+
+    let mut builder = MeshBuilder::default();
+    // binary files may also start with `solid`, so trust the size they declare
+    let is_binary = data.len() >= HEADER_LEN + 4 && {
+        let count = u32::from_le_bytes(data[HEADER_LEN..HEADER_LEN + 4].try_into().unwrap());
+        data.len() == HEADER_LEN + 4 + count as usize * FACET_LEN
+    };
+    if is_binary {
+        for facet in data[HEADER_LEN + 4..].chunks_exact(FACET_LEN) {
+            let float = |i: usize| {
+                let offset = 12 + 4 * i;
+                f32::from_le_bytes(facet[offset..offset + 4].try_into().unwrap())
+            };
+            builder.push_triangle([
+                Point::new(float(0), float(1), float(2)),
+                Point::new(float(3), float(4), float(5)),
+                Point::new(float(6), float(7), float(8)),
+            ]);
+        }
+    } else {
+        let text = str::from_utf8(&data).map_err(|_| invalid_data("not a valid STL file"))?;
+        parse_ascii(text, &mut builder)?;
+    }
+    Ok(Mesh::new(builder.positions, builder.indices))
+}
+
+fn parse_ascii(text: &str, builder: &mut MeshBuilder) -> io::Result<()> {
+    let mut facet = Vec::with_capacity(3);
+    for (i, line) in text.lines().enumerate() {
+        let n = i + 1;
+        let (keyword, args) = match split_line(line) {
+            Some(split) => split,
+            None => continue,
+        };
+        match keyword {
+            "vertex" => facet.push(parse_floats::<3>(n, args, 3)?.into()),
+            "endfacet" => {
+                let vertices = std::mem::take(&mut facet);
+                let triangle = vertices
+                    .try_into()
+                    .map_err(|_| invalid_line(n, "a facet needs exactly 3 vertices"))?;
+                builder.push_triangle(triangle);
+            }
+            "solid" | "facet" | "outer" | "endloop" | "endsolid" => {}
+            _ => return Err(invalid_line(n, "unexpected statement")),
+        }
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct MeshBuilder {
+    positions: Vec<Point>,
+    indices: Vec<[u32; 3]>,
+    // vertices are merged when their coordinates are bitwise equal
+    lookup: HashMap<[u32; 3], u32>,
+}
+
+impl MeshBuilder {
+    fn push_triangle(&mut self, triangle: [Point; 3]) {
+        let mut index = |p: Point| {
+            let key = [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()];
+            let positions = &mut self.positions;
+            *self.lookup.entry(key).or_insert_with(|| {
+                positions.push(p);
+                positions.len() as u32 - 1
+            })
+        };
+        let face = [index(triangle[0]), index(triangle[1]), index(triangle[2])];
+        self.indices.push(face);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::ray::Ray;
+    use crate::geometry::Shape;
+    use crate::loader::parse_stl;
+    use crate::prelude::*;
+    use nalgebra::point;
+    use std::io::ErrorKind;
+
+    const ASCII: &str = "\
+solid square
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 1 1 0
+    endloop
+  endfacet
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 1 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid square
+";
+
+    fn binary(facets: &[[[f32; 3]; 3]]) -> Vec<u8> {
+        // a header starting with `solid` must not fool the parser
+        let mut data = b"solid but actually binary".to_vec();
+        data.resize(80, 0);
+        data.extend_from_slice(&(facets.len() as u32).to_le_bytes());
+        for facet in facets {
+            data.extend_from_slice(&[0; 12]);
+            for v in facet.iter().flatten() {
+                data.extend_from_slice(&v.to_le_bytes());
+            }
+            data.extend_from_slice(&[0; 2]);
+        }
+        data
+    }
+
+    #[test]
+    fn test_ascii() {
+        let mesh = parse_stl(ASCII.as_bytes()).unwrap();
+        assert_eq!(mesh.len(), 2);
+        // the shared diagonal is merged
+        assert_eq!(mesh.positions().len(), 4);
+        assert_eq!(mesh.indices(), [[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(mesh.positions()[3], point![0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_binary() {
+        let data = binary(&[
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]],
+            [[0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+        ]);
+        let mesh = parse_stl(data.as_slice()).unwrap();
+        assert_eq!(mesh, parse_stl(ASCII.as_bytes()).unwrap());
+
+        let r = Ray::new([0.25, 0.75, 1.0], [0.0, 0.0, -1.0]);
+        let hits = mesh.intersect(r);
+        assert_eq!(hits[0].data.primitive, Some(1));
+        assert_eq!(
+            mesh.normal_at_hit(r.trace(1.0), &hits[0].data),
+            Vector::z_axis()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let bad = ASCII.replace("      vertex 1 0 0\n", "");
+        let err = parse_stl(bad.as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "line 7: a facet needs exactly 3 vertices");
+
+        let mut truncated = binary(&[[[0.0; 3]; 3]]);
+        truncated.pop();
+        truncated[0] = 0xff;
+        assert_eq!(
+            parse_stl(truncated.as_slice()).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+}
@@ -17,6 +17,81 @@ pub trait InverseTransform<T: Scalar + Clone, const D: usize> {
     fn inverse_transform_vector(&self, v: &SVector<T, D>) -> SVector<T, D>;
 }
 
+/// A transform that can be undone by another of the same type, unless it is
+/// singular.
+pub trait TryInverse: Sized {
+    fn try_inverse(&self) -> Option<Self>;
+}
+
+impl<T: RealField, C: TCategory, const D: usize> TryInverse for Transform<T, C, D>
+where
+    Const<D>: DimNameAdd<U1>,
+    C: SubTCategoryOf<TProjective>,
+    DefaultAllocator: Allocator<T, DimNameSum<Const<D>, U1>, DimNameSum<Const<D>, U1>>
+        + Allocator<T, DimNameSum<Const<D>, U1>>,
+{
+    fn try_inverse(&self) -> Option<Self> {
+        self.clone().try_inverse()
+    }
+}
+
+impl<T: SimdRealField, const D: usize> TryInverse for Translation<T, D> {
+    fn try_inverse(&self) -> Option<Self> {
+        Some(self.inverse())
+    }
+}
+
+impl<T: Scalar, const D: usize> TryInverse for Rotation<T, D> {
+    fn try_inverse(&self) -> Option<Self> {
+        Some(self.inverse())
+    }
+}
+
+impl<T: SimdRealField> TryInverse for UnitComplex<T>
+where
+    T::Element: SimdRealField,
+{
+    fn try_inverse(&self) -> Option<Self> {
+        Some(self.inverse())
+    }
+}
+
+impl<T: SimdRealField> TryInverse for UnitQuaternion<T>
+where
+    T::Element: SimdRealField,
+{
+    fn try_inverse(&self) -> Option<Self> {
+        Some(self.inverse())
+    }
+}
+
+impl<T: SimdRealField> TryInverse for UnitDualQuaternion<T>
+where
+    T::Element: SimdRealField,
+{
+    fn try_inverse(&self) -> Option<Self> {
+        Some(self.inverse())
+    }
+}
+
+impl<T: SimdRealField, R: AbstractRotation<T, D>, const D: usize> TryInverse for Isometry<T, R, D>
+where
+    T::Element: SimdRealField,
+{
+    fn try_inverse(&self) -> Option<Self> {
+        Some(self.inverse())
+    }
+}
+
+impl<T: SimdRealField, R: AbstractRotation<T, D>, const D: usize> TryInverse for Similarity<T, R, D>
+where
+    T::Element: SimdRealField,
+{
+    fn try_inverse(&self) -> Option<Self> {
+        Some(self.inverse())
+    }
+}
+
 impl<T: RealField, C: TCategory, const D: usize> GenericTransform<T, D> for Transform<T, C, D>
 where
     Const<D>: DimNameAdd<U1>,